@@ -0,0 +1,169 @@
+#ifndef TAC_TOE_H
+#define TAC_TOE_H
+
+/* Generated by cbindgen from ffi-tac-toe; do not edit. */
+
+#include <stdint.h>
+
+/**
+ * The call succeeded.
+ */
+#define TTT_OK 0
+
+/**
+ * A handle or out pointer was null.
+ */
+#define TTT_ERROR_NULL -1
+
+/**
+ * A coordinate is not between 0 and 2.
+ */
+#define TTT_ERROR_OUT_OF_BOUNDS -2
+
+/**
+ * The cell is already marked.
+ */
+#define TTT_ERROR_CELL_MARKED -3
+
+/**
+ * The game has already finished.
+ */
+#define TTT_ERROR_GAME_ENDED -4
+
+/**
+ * Nobody can mark the cell.
+ */
+#define TTT_ERROR_CELL_BLOCKED -5
+
+/**
+ * An empty cell.
+ */
+#define TTT_EMPTY 0
+
+/**
+ * The X player, or a cell marked by it.
+ */
+#define TTT_X 1
+
+/**
+ * The 0 player, or a cell marked by it.
+ */
+#define TTT_0 2
+
+/**
+ * A cell that nobody can mark.
+ */
+#define TTT_BLOCKED 3
+
+/**
+ * The game goes on.
+ */
+#define TTT_PLAYING 0
+
+#define TTT_X_WON 1
+
+#define TTT_0_WON 2
+
+#define TTT_DRAW 3
+
+/**
+ * Plays perfectly, picking at random between equally good moves.
+ */
+typedef struct TttAi TttAi;
+
+/**
+ * A game on the classic board, X moving first.
+ */
+typedef struct TttGame TttGame;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * A new game handle. Game handles from here and from
+ * `ttt_game_new_blocked` are live until passed to `ttt_game_free`.
+ */
+TttGame *ttt_game_new(void);
+
+/**
+ * A game with `count` cells blocked at random, repeatable for the
+ * same `seed`.
+ */
+TttGame *ttt_game_new_blocked(uint32_t count, uint64_t seed);
+
+/**
+ * # Safety
+ *
+ * `game` must be null or a live game handle; it is no longer live
+ * afterwards.
+ */
+void ttt_game_free(TttGame *game);
+
+/**
+ * Marks the cell for the player whose turn it is.
+ *
+ * # Safety
+ *
+ * `game` must be null or a live game handle.
+ */
+int32_t ttt_game_mark(TttGame *game, uint32_t x, uint32_t y);
+
+/**
+ * `TTT_EMPTY`, `TTT_X`, `TTT_0` or `TTT_BLOCKED`, or an error code.
+ *
+ * # Safety
+ *
+ * `game` must be null or a live game handle.
+ */
+int32_t ttt_game_cell(const TttGame *game, uint32_t x, uint32_t y);
+
+/**
+ * The player to move: `TTT_X` or `TTT_0`, or `TTT_ERROR_NULL`.
+ *
+ * # Safety
+ *
+ * `game` must be null or a live game handle.
+ */
+int32_t ttt_game_turn(const TttGame *game);
+
+/**
+ * `TTT_PLAYING`, `TTT_X_WON`, `TTT_0_WON` or `TTT_DRAW`, or
+ * `TTT_ERROR_NULL`.
+ *
+ * # Safety
+ *
+ * `game` must be null or a live game handle.
+ */
+int32_t ttt_game_outcome(const TttGame *game);
+
+/**
+ * An AI whose choices between equally good moves are repeatable
+ * for the same `seed`.
+ */
+TttAi *ttt_ai_new(uint64_t seed);
+
+/**
+ * # Safety
+ *
+ * `ai` must be null or come from `ttt_ai_new` and not have been
+ * freed yet.
+ */
+void ttt_ai_free(TttAi *ai);
+
+/**
+ * Writes the AI's move for the player to move to `x` and `y`,
+ * without making it.
+ *
+ * # Safety
+ *
+ * `ai` and `game` must be null or live handles, `x` and `y` null or
+ * valid for writes.
+ */
+int32_t ttt_ai_move(TttAi *ai, const TttGame *game, uint32_t *x, uint32_t *y);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* TAC_TOE_H */
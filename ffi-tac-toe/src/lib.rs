@@ -0,0 +1,219 @@
+//! C bindings for lib-tac-toe; the header is `include/tac_toe.h`.
+//!
+//! Games and AIs are opaque handles, created by `ttt_*_new` and
+//! released by `ttt_*_free`. Functions that can fail return `TTT_OK`
+//! or one of the negative `TTT_ERROR_*` codes.
+use lib_tac_toe::errors::MarkError;
+use lib_tac_toe::player::{PerfectPlayer, Player};
+use lib_tac_toe::{self as lib, CellState, GameBuilder, Outcome, XorZ};
+
+/// The call succeeded.
+pub const TTT_OK: i32 = 0;
+/// A handle or out pointer was null.
+pub const TTT_ERROR_NULL: i32 = -1;
+/// A coordinate is not between 0 and 2.
+pub const TTT_ERROR_OUT_OF_BOUNDS: i32 = -2;
+/// The cell is already marked.
+pub const TTT_ERROR_CELL_MARKED: i32 = -3;
+/// The game has already finished.
+pub const TTT_ERROR_GAME_ENDED: i32 = -4;
+/// Nobody can mark the cell.
+pub const TTT_ERROR_CELL_BLOCKED: i32 = -5;
+
+/// An empty cell.
+pub const TTT_EMPTY: i32 = 0;
+/// The X player, or a cell marked by it.
+pub const TTT_X: i32 = 1;
+/// The 0 player, or a cell marked by it.
+pub const TTT_0: i32 = 2;
+/// A cell that nobody can mark.
+pub const TTT_BLOCKED: i32 = 3;
+
+/// The game goes on.
+pub const TTT_PLAYING: i32 = 0;
+pub const TTT_X_WON: i32 = 1;
+pub const TTT_0_WON: i32 = 2;
+pub const TTT_DRAW: i32 = 3;
+
+const ROW_SIZE: u32 = 3;
+
+/// A game on the classic board, X moving first.
+pub struct TttGame {
+    game: lib::Game,
+}
+
+/// Plays perfectly, picking at random between equally good moves.
+pub struct TttAi {
+    player: PerfectPlayer,
+}
+
+/// A new game handle. Game handles from here and from
+/// `ttt_game_new_blocked` are live until passed to `ttt_game_free`.
+#[no_mangle]
+pub extern "C" fn ttt_game_new() -> *mut TttGame {
+    Box::into_raw(Box::new(TttGame {
+        game: lib::Game::new(),
+    }))
+}
+
+/// A game with `count` cells blocked at random, repeatable for the
+/// same `seed`.
+#[no_mangle]
+pub extern "C" fn ttt_game_new_blocked(count: u32, seed: u64) -> *mut TttGame {
+    let game = GameBuilder::new()
+        .with_random_blocked_cells(count as usize, seed)
+        .build()
+        .unwrap();
+
+    Box::into_raw(Box::new(TttGame { game }))
+}
+
+/// # Safety
+///
+/// `game` must be null or a live game handle; it is no longer live
+/// afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn ttt_game_free(game: *mut TttGame) {
+    if !game.is_null() {
+        drop(Box::from_raw(game));
+    }
+}
+
+/// Marks the cell for the player whose turn it is.
+///
+/// # Safety
+///
+/// `game` must be null or a live game handle.
+#[no_mangle]
+pub unsafe extern "C" fn ttt_game_mark(game: *mut TttGame, x: u32, y: u32) -> i32 {
+    let game = match game.as_mut() {
+        Some(game) => &mut game.game,
+        None => return TTT_ERROR_NULL,
+    };
+
+    match game.mark(x as usize, y as usize) {
+        Ok(_) => TTT_OK,
+        Err(MarkError::OutOfBounds { .. }) => TTT_ERROR_OUT_OF_BOUNDS,
+        Err(MarkError::CellMarked) => TTT_ERROR_CELL_MARKED,
+        Err(MarkError::CellBlocked) => TTT_ERROR_CELL_BLOCKED,
+        Err(MarkError::GameEnded) => TTT_ERROR_GAME_ENDED,
+    }
+}
+
+/// `TTT_EMPTY`, `TTT_X`, `TTT_0` or `TTT_BLOCKED`, or an error code.
+///
+/// # Safety
+///
+/// `game` must be null or a live game handle.
+#[no_mangle]
+pub unsafe extern "C" fn ttt_game_cell(game: *const TttGame, x: u32, y: u32) -> i32 {
+    let game = match game.as_ref() {
+        Some(game) => &game.game,
+        None => return TTT_ERROR_NULL,
+    };
+
+    if x >= ROW_SIZE || y >= ROW_SIZE {
+        return TTT_ERROR_OUT_OF_BOUNDS;
+    }
+
+    match game.board_state()[x as usize][y as usize] {
+        CellState::Empty => TTT_EMPTY,
+        CellState::Marked(player) => player_code(player),
+        CellState::Blocked => TTT_BLOCKED,
+    }
+}
+
+/// The player to move: `TTT_X` or `TTT_0`, or `TTT_ERROR_NULL`.
+///
+/// # Safety
+///
+/// `game` must be null or a live game handle.
+#[no_mangle]
+pub unsafe extern "C" fn ttt_game_turn(game: *const TttGame) -> i32 {
+    match game.as_ref() {
+        Some(game) => player_code(game.game.turn()),
+        None => TTT_ERROR_NULL,
+    }
+}
+
+/// `TTT_PLAYING`, `TTT_X_WON`, `TTT_0_WON` or `TTT_DRAW`, or
+/// `TTT_ERROR_NULL`.
+///
+/// # Safety
+///
+/// `game` must be null or a live game handle.
+#[no_mangle]
+pub unsafe extern "C" fn ttt_game_outcome(game: *const TttGame) -> i32 {
+    let game = match game.as_ref() {
+        Some(game) => &game.game,
+        None => return TTT_ERROR_NULL,
+    };
+
+    match game.get_outcome().map(Outcome::winner) {
+        None => TTT_PLAYING,
+        Some(Some(XorZ::X)) => TTT_X_WON,
+        Some(Some(XorZ::Z)) => TTT_0_WON,
+        Some(None) => TTT_DRAW,
+    }
+}
+
+/// An AI whose choices between equally good moves are repeatable
+/// for the same `seed`.
+#[no_mangle]
+pub extern "C" fn ttt_ai_new(seed: u64) -> *mut TttAi {
+    Box::into_raw(Box::new(TttAi {
+        player: PerfectPlayer::new(seed),
+    }))
+}
+
+/// # Safety
+///
+/// `ai` must be null or come from `ttt_ai_new` and not have been
+/// freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn ttt_ai_free(ai: *mut TttAi) {
+    if !ai.is_null() {
+        drop(Box::from_raw(ai));
+    }
+}
+
+/// Writes the AI's move for the player to move to `x` and `y`,
+/// without making it.
+///
+/// # Safety
+///
+/// `ai` and `game` must be null or live handles, `x` and `y` null or
+/// valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn ttt_ai_move(
+    ai: *mut TttAi,
+    game: *const TttGame,
+    x: *mut u32,
+    y: *mut u32,
+) -> i32 {
+    let (ai, game) = match (ai.as_mut(), game.as_ref()) {
+        (Some(ai), Some(game)) => (ai, &game.game),
+        _ => return TTT_ERROR_NULL,
+    };
+
+    if x.is_null() || y.is_null() {
+        return TTT_ERROR_NULL;
+    }
+
+    match ai.player.choose_move(game) {
+        Ok(Some((cell_x, cell_y))) => {
+            *x = cell_x as u32;
+            *y = cell_y as u32;
+
+            TTT_OK
+        }
+        _ => TTT_ERROR_GAME_ENDED,
+    }
+}
+
+fn player_code(player: XorZ) -> i32 {
+    match player {
+        XorZ::X => TTT_X,
+        XorZ::Z => TTT_0,
+    }
+}
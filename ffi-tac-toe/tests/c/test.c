@@ -0,0 +1,100 @@
+/* Plays games through the C API; exits with 1 on the first failed check. */
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "tac_toe.h"
+
+#define CHECK(condition)                                                      \
+    do {                                                                      \
+        if (!(condition)) {                                                   \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, \
+                    #condition);                                              \
+            exit(1);                                                          \
+        }                                                                     \
+    } while (0)
+
+static void marks_and_errors(void) {
+    TttGame *game = ttt_game_new();
+
+    CHECK(ttt_game_turn(game) == TTT_X);
+    CHECK(ttt_game_mark(game, 1, 1) == TTT_OK);
+    CHECK(ttt_game_cell(game, 1, 1) == TTT_X);
+    CHECK(ttt_game_cell(game, 0, 0) == TTT_EMPTY);
+    CHECK(ttt_game_turn(game) == TTT_0);
+
+    CHECK(ttt_game_mark(game, 1, 1) == TTT_ERROR_CELL_MARKED);
+    CHECK(ttt_game_mark(game, 3, 0) == TTT_ERROR_OUT_OF_BOUNDS);
+    CHECK(ttt_game_cell(game, 0, 3) == TTT_ERROR_OUT_OF_BOUNDS);
+    CHECK(ttt_game_mark(NULL, 0, 0) == TTT_ERROR_NULL);
+
+    /* X completes the middle column */
+    CHECK(ttt_game_mark(game, 0, 0) == TTT_OK);
+    CHECK(ttt_game_mark(game, 1, 0) == TTT_OK);
+    CHECK(ttt_game_mark(game, 2, 2) == TTT_OK);
+    CHECK(ttt_game_outcome(game) == TTT_PLAYING);
+    CHECK(ttt_game_mark(game, 1, 2) == TTT_OK);
+
+    CHECK(ttt_game_outcome(game) == TTT_X_WON);
+    CHECK(ttt_game_mark(game, 2, 0) == TTT_ERROR_GAME_ENDED);
+
+    ttt_game_free(game);
+    ttt_game_free(NULL);
+}
+
+static void ais_draw(void) {
+    TttGame *game = ttt_game_new();
+    TttAi *ai = ttt_ai_new(7);
+    uint32_t x, y;
+
+    while (ttt_game_outcome(game) == TTT_PLAYING) {
+        CHECK(ttt_ai_move(ai, game, &x, &y) == TTT_OK);
+        CHECK(ttt_game_mark(game, x, y) == TTT_OK);
+    }
+
+    CHECK(ttt_game_outcome(game) == TTT_DRAW);
+    CHECK(ttt_ai_move(ai, game, &x, &y) == TTT_ERROR_GAME_ENDED);
+    CHECK(ttt_ai_move(ai, game, NULL, &y) == TTT_ERROR_NULL);
+
+    ttt_ai_free(ai);
+    ttt_game_free(game);
+}
+
+static void blocked_cells(void) {
+    TttGame *game = ttt_game_new_blocked(9, 1);
+
+    CHECK(ttt_game_cell(game, 1, 1) == TTT_BLOCKED);
+    CHECK(ttt_game_outcome(game) == TTT_DRAW);
+    ttt_game_free(game);
+
+    game = ttt_game_new_blocked(2, 1);
+    TttAi *ai = ttt_ai_new(3);
+    uint32_t x, y, blocked = 0;
+
+    for (x = 0; x < 3; x++) {
+        for (y = 0; y < 3; y++) {
+            if (ttt_game_cell(game, x, y) == TTT_BLOCKED) {
+                CHECK(ttt_game_mark(game, x, y) == TTT_ERROR_CELL_BLOCKED);
+                blocked++;
+            }
+        }
+    }
+
+    CHECK(blocked == 2);
+
+    /* the AI never picks a blocked cell */
+    while (ttt_game_outcome(game) == TTT_PLAYING) {
+        CHECK(ttt_ai_move(ai, game, &x, &y) == TTT_OK);
+        CHECK(ttt_game_mark(game, x, y) == TTT_OK);
+    }
+
+    ttt_ai_free(ai);
+    ttt_game_free(game);
+}
+
+int main(void) {
+    marks_and_errors();
+    ais_draw();
+    blocked_cells();
+
+    return 0;
+}
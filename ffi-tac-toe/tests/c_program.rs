@@ -0,0 +1,36 @@
+//! Builds `tests/c/test.c` against the shared library and runs it.
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+#[test]
+fn c_program() {
+    let crate_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+
+    // the test runs from target/<profile>/deps, next to the library
+    let exe = env::current_exe().unwrap();
+    let lib_dir = exe.parent().unwrap();
+    let program = lib_dir.join("tac_toe_c_test");
+
+    let status = Command::new(env::var("CC").unwrap_or_else(|_| "cc".to_string()))
+        .arg(crate_dir.join("tests/c/test.c"))
+        .arg("-I")
+        .arg(crate_dir.join("include"))
+        .arg("-L")
+        .arg(lib_dir)
+        .arg("-lffi_tac_toe")
+        .arg("-o")
+        .arg(&program)
+        .status()
+        .expect("C compiler not found; set CC");
+
+    assert!(status.success(), "compiling the C test failed");
+
+    let status = Command::new(&program)
+        .env("LD_LIBRARY_PATH", lib_dir)
+        .env("DYLD_LIBRARY_PATH", lib_dir)
+        .status()
+        .unwrap();
+
+    assert!(status.success(), "the C test failed");
+}
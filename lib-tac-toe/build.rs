@@ -0,0 +1,138 @@
+//! Generates the perfect play table of the classic board used
+//! by the `tablebase` module.
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+#[allow(dead_code)]
+#[path = "src/symmetry.rs"]
+mod symmetry;
+
+const FULL: u16 = 0b111_111_111;
+const WIN_MASKS: [u16; 8] = [
+    0b100_010_001,
+    0b001_010_100,
+    0b001_001_001,
+    0b010_010_010,
+    0b100_100_100,
+    0b000_000_111,
+    0b000_111_000,
+    0b111_000_000,
+];
+
+// value bits of a table entry; see `tablebase::Entry`
+const DRAW: u16 = 1;
+const X_WINS: u16 = 2;
+const Z_WINS: u16 = 3;
+
+/// Perfect play result of a position.
+#[derive(Clone, Copy)]
+struct Solved {
+    value: u16,
+    /// plies until the game ends
+    distance: u16,
+    best_moves: u16,
+}
+
+fn main() {
+    let mut solved = HashMap::new();
+    solve(0, 0, &mut solved);
+
+    let mut table = vec![0u16; symmetry::POSITIONS];
+
+    for (&(x, z), s) in solved.iter() {
+        let (cx, cz, _) = symmetry::canonical(x, z);
+
+        // symmetric positions are solved too, so only canonical
+        // ones need to be stored
+        if (cx, cz) == (x, z) {
+            table[symmetry::index(x, z)] = s.best_moves | s.value << 9 | s.distance << 11;
+        }
+    }
+
+    let entries: Vec<_> = table.iter().map(u16::to_string).collect();
+    let code = format!(
+        "static TABLE: [u16; {}] = [{}];\n",
+        table.len(),
+        entries.join(",")
+    );
+
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("tablebase.rs");
+    fs::write(out, code).unwrap();
+
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/symmetry.rs");
+}
+
+fn wins(marks: u16) -> bool {
+    WIN_MASKS.iter().any(|m| marks & m == *m)
+}
+
+/// Solves every position reachable from `x`, `z` with X moving first.
+fn solve(x: u16, z: u16, solved: &mut HashMap<(u16, u16), Solved>) -> Solved {
+    if let Some(s) = solved.get(&(x, z)) {
+        return *s;
+    }
+
+    let x_to_move = x.count_ones() == z.count_ones();
+    let ended = |value| Solved {
+        value,
+        distance: 0,
+        best_moves: 0,
+    };
+
+    let result = if wins(x) {
+        ended(X_WINS)
+    } else if wins(z) {
+        ended(Z_WINS)
+    } else if x | z == FULL {
+        ended(DRAW)
+    } else {
+        let (own_win, other_win) = if x_to_move {
+            (X_WINS, Z_WINS)
+        } else {
+            (Z_WINS, X_WINS)
+        };
+
+        // higher is better for the player to move: win quickly,
+        // lose slowly
+        let rank = |s: &Solved| match s.value {
+            v if v == own_win => (2, -(s.distance as i16)),
+            v if v == other_win => (0, s.distance as i16),
+            _ => (1, 0),
+        };
+
+        let children: Vec<_> = (0..9)
+            .filter(|cell| (x | z) & (1 << cell) == 0)
+            .map(|cell| {
+                let child = if x_to_move {
+                    solve(x | 1 << cell, z, solved)
+                } else {
+                    solve(x, z | 1 << cell, solved)
+                };
+
+                (cell, child)
+            })
+            .collect();
+
+        let best = children
+            .iter()
+            .map(|(_, c)| c)
+            .max_by_key(|c| rank(c))
+            .unwrap();
+
+        Solved {
+            value: best.value,
+            distance: best.distance + 1,
+            best_moves: children
+                .iter()
+                .filter(|(_, c)| c.value == best.value)
+                .fold(0, |mask, (cell, _)| mask | 1 << cell),
+        }
+    };
+
+    solved.insert((x, z), result);
+
+    result
+}
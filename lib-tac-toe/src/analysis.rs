@@ -0,0 +1,163 @@
+//! Judges every move of a game against perfect play.
+use alloc::format;
+
+use super::record::{cell_name, Annotation, GameRecord};
+use super::solver::{self, Value};
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// Keeps the best result the player could still get.
+    Best,
+    /// Lets a won game slip into a draw.
+    Inaccuracy,
+    /// Loses a game that could have been drawn or won.
+    Blunder,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MoveAnalysis {
+    pub cell: CellCoord,
+    pub player: XorZ,
+    pub verdict: Verdict,
+    /// every move that would have been best
+    pub best_moves: Vec<CellCoord>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameAnalysis {
+    pub moves: Vec<MoveAnalysis>,
+}
+
+impl GameAnalysis {
+    /// Share of `player`'s moves that were best; 1 if it made none.
+    pub fn accuracy(&self, player: XorZ) -> f64 {
+        let (best, total) = self
+            .moves
+            .iter()
+            .filter(|m| m.player == player)
+            .fold((0, 0), |(best, total), m| {
+                (best + (m.verdict == Verdict::Best) as usize, total + 1)
+            });
+
+        if total == 0 {
+            1.0
+        } else {
+            best as f64 / total as f64
+        }
+    }
+
+    pub fn mistakes(&self) -> impl Iterator<Item = &MoveAnalysis> {
+        self.moves.iter().filter(|m| m.verdict != Verdict::Best)
+    }
+}
+
+/// Replays the record, comparing each move with the solver's.
+pub fn analyse(record: &GameRecord) -> Result<GameAnalysis, Error> {
+    let mut game = record.new_game()?;
+
+    let mut moves = Vec::new();
+
+    for m in &record.moves {
+        let player = game.turn();
+        let before = solver::value(&game);
+        let best_moves = solver::best_moves(&game);
+
+        game.mark(m.cell.0, m.cell.1)?;
+
+        let after = match game.get_outcome() {
+            Some(outcome) => match outcome.winner() {
+                Some(winner) => Value::Win(winner),
+                None => Value::Draw,
+            },
+            None => solver::value(&game),
+        };
+
+        let verdict = if after == before {
+            Verdict::Best
+        } else if after == Value::Win(player.other()) {
+            Verdict::Blunder
+        } else {
+            Verdict::Inaccuracy
+        };
+
+        moves.push(MoveAnalysis {
+            cell: m.cell,
+            player,
+            verdict,
+            best_moves,
+        });
+    }
+
+    Ok(GameAnalysis { moves })
+}
+
+/// Marks inaccuracies with `?!` and blunders with `??`, comments on
+/// them with the best moves and adds each player's accuracy as tags.
+pub fn annotate(record: &mut GameRecord, analysis: &GameAnalysis) {
+    for (m, a) in record.moves.iter_mut().zip(&analysis.moves) {
+        let annotation = match a.verdict {
+            Verdict::Best => continue,
+            Verdict::Inaccuracy => Annotation::Dubious,
+            Verdict::Blunder => Annotation::Blunder,
+        };
+
+        let best: Vec<_> = a.best_moves.iter().map(|c| cell_name(*c)).collect();
+
+        m.annotation = Some(annotation);
+        m.comment = Some(format!("best: {}", best.join(", ")));
+    }
+
+    for (tag, player) in &[("AccuracyX", XorZ::X), ("Accuracy0", XorZ::Z)] {
+        let percent = format!("{:.0}%", analysis.accuracy(*player) * 100.0);
+
+        record.set_tag(tag, &percent);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn blunder() {
+        // 0 answers the center with an edge and loses to the fork
+        let record = GameRecord::read("1. b2 b1 2. a1 c3 3. a3 a2 4. c1 1-0").unwrap();
+        let analysis = analyse(&record).unwrap();
+
+        let blunders: Vec<_> = analysis.mistakes().map(|m| m.cell).collect();
+
+        assert_eq!(blunders, vec![(1, 0)]);
+        assert_eq!(analysis.moves[1].verdict, Verdict::Blunder);
+        assert_eq!(analysis.accuracy(XorZ::X), 1.0);
+        assert!((analysis.accuracy(XorZ::Z) - 2.0 / 3.0).abs() < 1e-9);
+
+        let mut annotated = record.clone();
+        annotate(&mut annotated, &analysis);
+
+        assert_eq!(annotated.moves[1].annotation, Some(Annotation::Blunder));
+        assert_eq!(
+            annotated.moves[1].comment.as_deref(),
+            Some("best: a1, c1, a3, c3")
+        );
+        assert_eq!(annotated.tag("Accuracy0"), Some("67%"));
+        assert!(GameRecord::read(&annotated.to_string()).is_ok());
+    }
+
+    #[test]
+    fn missed_win() {
+        // 0 should have taken the center; X then lets the win slip,
+        // and 0 misses the center again
+        let record = GameRecord::read("1. a1 b1 2. c3 c1 3. a3 a2 *").unwrap();
+        let analysis = analyse(&record).unwrap();
+
+        let verdicts: Vec<_> = analysis.moves.iter().map(|m| m.verdict).collect();
+
+        use Verdict::*;
+        assert_eq!(
+            verdicts,
+            vec![Best, Blunder, Inaccuracy, Blunder, Best, Best]
+        );
+        assert_eq!(analysis.moves[2].best_moves, vec![(0, 1), (1, 1), (0, 2)]);
+    }
+}
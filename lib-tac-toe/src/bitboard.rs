@@ -0,0 +1,401 @@
+//! Boards stored as one bit mask per player, for fast search.
+//!
+//! Cell `x`, `y` of a board with rows of length `size` is
+//! bit `y * size + x` of the masks.
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+use core::ops::{BitAnd, BitOr, Not};
+
+use super::{symmetry, CellState, XorZ};
+
+/// Integer types that can hold one bit per cell.
+pub trait Mask:
+    Copy
+    + Default
+    + Debug
+    + Eq
+    + Ord
+    + Hash
+    + BitAnd<Output = Self>
+    + BitOr<Output = Self>
+    + Not<Output = Self>
+{
+    const BITS: usize;
+
+    fn bit(index: usize) -> Self;
+
+    fn count(self) -> u32;
+
+    /// Index of the lowest set bit.
+    fn lowest(self) -> Option<usize>;
+
+    fn is_empty(self) -> bool {
+        self == Self::default()
+    }
+
+    fn contains(self, other: Self) -> bool {
+        self & other == other
+    }
+}
+
+macro_rules! impl_mask {
+    ($($t:ty),*) => {
+        $(
+            impl Mask for $t {
+                const BITS: usize = <$t>::BITS as usize;
+
+                fn bit(index: usize) -> Self {
+                    1 << index
+                }
+
+                fn count(self) -> u32 {
+                    self.count_ones()
+                }
+
+                fn lowest(self) -> Option<usize> {
+                    if self == 0 {
+                        None
+                    } else {
+                        Some(self.trailing_zeros() as usize)
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_mask!(u16, u32, u64, u128);
+
+/// 256 bits, lowest word first, for boards of up to 16x16 cells
+/// such as gomoku's 15x15.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct U256([u64; 4]);
+
+impl BitAnd for U256 {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self {
+        let mut words = self.0;
+
+        for (word, other) in words.iter_mut().zip(other.0.iter()) {
+            *word &= other;
+        }
+
+        U256(words)
+    }
+}
+
+impl BitOr for U256 {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        let mut words = self.0;
+
+        for (word, other) in words.iter_mut().zip(other.0.iter()) {
+            *word |= other;
+        }
+
+        U256(words)
+    }
+}
+
+impl Not for U256 {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        let [a, b, c, d] = self.0;
+
+        U256([!a, !b, !c, !d])
+    }
+}
+
+impl Mask for U256 {
+    const BITS: usize = 256;
+
+    fn bit(index: usize) -> Self {
+        let mut words = [0; 4];
+        words[index / 64] = 1 << (index % 64);
+
+        U256(words)
+    }
+
+    fn count(self) -> u32 {
+        self.0.iter().map(|word| word.count_ones()).sum()
+    }
+
+    fn lowest(self) -> Option<usize> {
+        self.0
+            .iter()
+            .enumerate()
+            .find(|(_, word)| **word != 0)
+            .map(|(i, word)| i * 64 + word.trailing_zeros() as usize)
+    }
+}
+
+/// Win masks of the classic 3x3 board, in `WinLine::ALL` order.
+pub const CLASSIC_WIN_MASKS: [u16; 8] = [
+    0b100_010_001,
+    0b001_010_100,
+    0b001_001_001,
+    0b010_010_010,
+    0b100_100_100,
+    0b000_000_111,
+    0b000_111_000,
+    0b111_000_000,
+];
+
+/// Mask of all cells of the classic board.
+pub const CLASSIC_FULL: u16 = 0b111_111_111;
+
+/// Shape of a square board: the length of a row and how many
+/// marks in a row are needed to win.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Geometry {
+    pub size: usize,
+    pub in_a_row: usize,
+}
+
+impl Geometry {
+    pub const CLASSIC: Geometry = Geometry {
+        size: 3,
+        in_a_row: 3,
+    };
+
+    pub fn new(size: usize, in_a_row: usize) -> Self {
+        assert!(in_a_row > 0 && in_a_row <= size);
+
+        Self { size, in_a_row }
+    }
+
+    pub fn cells(&self) -> usize {
+        self.size * self.size
+    }
+
+    pub fn index(&self, x: usize, y: usize) -> usize {
+        y * self.size + x
+    }
+
+    pub fn coord(&self, index: usize) -> (usize, usize) {
+        (index % self.size, index / self.size)
+    }
+
+    /// Every run of `in_a_row` cells in a row, column or diagonal.
+    ///
+    /// Panics if `M` has less bits than the board has cells.
+    pub fn win_masks<M: Mask>(&self) -> Vec<M> {
+        assert!(self.cells() <= M::BITS, "board does not fit the mask");
+
+        let (size, len) = (self.size as isize, self.in_a_row as isize);
+        let directions = [(1, 0), (0, 1), (1, 1), (1, -1)];
+        let mut masks = Vec::new();
+
+        for y in 0..size {
+            for x in 0..size {
+                for (dx, dy) in directions.iter() {
+                    let (end_x, end_y) = (x + dx * (len - 1), y + dy * (len - 1));
+
+                    if end_x < 0 || end_x >= size || end_y < 0 || end_y >= size {
+                        continue;
+                    }
+
+                    let mask = (0..len).fold(M::default(), |mask, i| {
+                        let index = self.index((x + dx * i) as usize, (y + dy * i) as usize);
+
+                        mask | M::bit(index)
+                    });
+
+                    masks.push(mask);
+                }
+            }
+        }
+
+        masks
+    }
+
+    /// Mask with a bit set for every cell.
+    pub fn full<M: Mask>(&self) -> M {
+        (0..self.cells()).fold(M::default(), |mask, i| mask | M::bit(i))
+    }
+
+    /// Cell that `index` is moved to by one of the `SYMMETRIES`
+    /// of the square, see `symmetry::transform`.
+    pub fn transform(&self, symmetry: usize, index: usize) -> usize {
+        symmetry::transform(self.size, symmetry, index)
+    }
+
+    /// Cell that `transform` moves to `index`.
+    pub fn untransform(&self, symmetry: usize, index: usize) -> usize {
+        self.transform(symmetry::inverse(symmetry), index)
+    }
+
+    /// The board's transformation with the smallest masks, and the
+    /// symmetry that produces it. Symmetric positions share their
+    /// canonical board.
+    pub fn canonical<M: Mask>(&self, board: &Bitboard<M>) -> (Bitboard<M>, usize) {
+        (0..SYMMETRIES)
+            .map(|symmetry| {
+                let mut transformed = Bitboard::new();
+
+                for player in [XorZ::X, XorZ::Z] {
+                    for cell in cells(board.marks(player)) {
+                        transformed.place(self.transform(symmetry, cell), player);
+                    }
+                }
+
+                (transformed, symmetry)
+            })
+            .min_by_key(|(b, _)| (b.x, b.z))
+            .unwrap()
+    }
+}
+
+/// Number of ways to rotate and mirror a square board.
+pub const SYMMETRIES: usize = symmetry::COUNT;
+
+/// Indexes of the bits set in `mask`, lowest first.
+pub fn cells<M: Mask>(mut mask: M) -> impl Iterator<Item = usize> {
+    core::iter::from_fn(move || {
+        let index = mask.lowest()?;
+        mask = mask & !M::bit(index);
+
+        Some(index)
+    })
+}
+
+/// Marks of both players, one bit per cell.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Bitboard<M: Mask> {
+    x: M,
+    z: M,
+}
+
+impl<M: Mask> Bitboard<M> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn marks(&self, player: XorZ) -> M {
+        match player {
+            XorZ::X => self.x,
+            XorZ::Z => self.z,
+        }
+    }
+
+    pub fn occupied(&self) -> M {
+        self.x | self.z
+    }
+
+    pub fn cell(&self, index: usize) -> CellState {
+        let bit = M::bit(index);
+
+        if self.x.contains(bit) {
+            CellState::Marked(XorZ::X)
+        } else if self.z.contains(bit) {
+            CellState::Marked(XorZ::Z)
+        } else {
+            CellState::Empty
+        }
+    }
+
+    /// Marks the cell at `index`, replacing any previous mark.
+    pub fn place(&mut self, index: usize, player: XorZ) {
+        self.clear(index);
+
+        match player {
+            XorZ::X => self.x = self.x | M::bit(index),
+            XorZ::Z => self.z = self.z | M::bit(index),
+        }
+    }
+
+    pub fn clear(&mut self, index: usize) {
+        let keep = !M::bit(index);
+
+        self.x = self.x & keep;
+        self.z = self.z & keep;
+    }
+
+    /// Does `player` own all cells of any of the masks?
+    pub fn wins(&self, player: XorZ, win_masks: &[M]) -> bool {
+        let marks = self.marks(player);
+
+        win_masks.iter().any(|mask| marks.contains(*mask))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn classic_win_masks() {
+        let mut generated = Geometry::CLASSIC.win_masks::<u16>();
+        let mut classic = CLASSIC_WIN_MASKS.to_vec();
+
+        generated.sort();
+        classic.sort();
+
+        assert_eq!(generated, classic);
+        assert_eq!(Geometry::CLASSIC.full::<u16>(), CLASSIC_FULL);
+    }
+
+    #[test]
+    fn bigger_boards() {
+        assert_eq!(Geometry::new(4, 4).win_masks::<u16>().len(), 10);
+        assert_eq!(Geometry::new(4, 3).win_masks::<u64>().len(), 24);
+        assert_eq!(Geometry::new(11, 5).win_masks::<u128>().len(), 252);
+        assert_eq!(Geometry::new(15, 5).win_masks::<U256>().len(), 572);
+    }
+
+    #[test]
+    fn symmetries() {
+        let geometry = Geometry::new(4, 3);
+        let mut board = Bitboard::<u16>::new();
+
+        board.place(geometry.index(1, 0), XorZ::X);
+        board.place(geometry.index(3, 3), XorZ::Z);
+
+        let (canonical, _) = geometry.canonical(&board);
+
+        for symmetry in 0..SYMMETRIES {
+            let mut transformed = Bitboard::<u16>::new();
+
+            transformed.place(geometry.transform(symmetry, geometry.index(1, 0)), XorZ::X);
+            transformed.place(geometry.transform(symmetry, geometry.index(3, 3)), XorZ::Z);
+
+            assert_eq!(geometry.canonical(&transformed).0, canonical);
+
+            for cell in 0..geometry.cells() {
+                assert_eq!(
+                    geometry.untransform(symmetry, geometry.transform(symmetry, cell)),
+                    cell
+                );
+            }
+        }
+
+        assert_eq!(cells(0b1010_0001u16).collect::<Vec<_>>(), vec![0, 5, 7]);
+    }
+
+    #[test]
+    fn place_and_clear() {
+        let mut b = Bitboard::<u64>::new();
+        let masks = Geometry::new(5, 4).win_masks::<u64>();
+
+        for i in 0..4 {
+            b.place(i * 6, XorZ::Z);
+        }
+
+        assert_eq!(b.cell(6), CellState::Marked(XorZ::Z));
+        assert!(b.wins(XorZ::Z, &masks));
+
+        b.place(6, XorZ::X);
+
+        assert_eq!(b.cell(6), CellState::Marked(XorZ::X));
+        assert!(!b.wins(XorZ::Z, &masks));
+
+        b.clear(6);
+
+        assert_eq!(b.cell(6), CellState::Empty);
+        assert_eq!(b.occupied().count(), 3);
+    }
+}
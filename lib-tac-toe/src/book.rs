@@ -0,0 +1,451 @@
+//! Opening books: recommended moves with weights for positions
+//! near the start of games on boards of any `Geometry`.
+//!
+//! Books are stored as text: a `size in_a_row` header line, then a
+//! line per position with its cells row by row (`X`, `0` or `.`),
+//! the player to move and the moves as `cell:weight` pairs:
+//!
+//! ```text
+//! 4 3
+//! ................ X 5:3 6:1
+//! .....X.......... 0 0:2
+//! ```
+//!
+//! Symmetric positions share an entry, stored for the canonical
+//! position.
+use alloc::collections::BTreeMap;
+use alloc::vec;
+#[cfg(feature = "std")]
+use std::fs;
+#[cfg(feature = "std")]
+use std::io::{BufRead, BufReader, Write};
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use super::bitboard::{cells, Bitboard, Geometry, Mask};
+use super::engine::Engine;
+use super::*;
+
+/// Added to a move of the winner of a self-play game.
+const WIN_WEIGHT: u32 = 2;
+/// Added to the moves of both players of a drawn self-play game.
+const DRAW_WEIGHT: u32 = 1;
+
+/// Cells and their weights.
+type Moves = Vec<(usize, u32)>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpeningBook<M: Mask> {
+    geometry: Geometry,
+    /// weighted cells by canonical position and player to move
+    entries: BTreeMap<(Bitboard<M>, XorZ), Moves>,
+}
+
+impl<M: Mask> OpeningBook<M> {
+    pub fn new(geometry: Geometry) -> Self {
+        Self {
+            geometry,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    pub fn geometry(&self) -> Geometry {
+        self.geometry
+    }
+
+    /// Number of positions in the book.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Adds `weight` to the move, adding the move if it is new.
+    /// Weights stop growing at `u32::MAX`.
+    pub fn add(&mut self, board: &Bitboard<M>, player: XorZ, cell: usize, weight: u32) {
+        let (canonical, symmetry) = self.geometry.canonical(board);
+        let cell = self.geometry.transform(symmetry, cell);
+        let moves = self.entries.entry((canonical, player)).or_default();
+
+        match moves.iter_mut().find(|(c, _)| *c == cell) {
+            Some((_, w)) => *w = w.saturating_add(weight),
+            None => moves.push((cell, weight)),
+        }
+    }
+
+    /// Book moves for the position, with their weights.
+    pub fn moves(&self, board: &Bitboard<M>, player: XorZ) -> Moves {
+        let (canonical, symmetry) = self.geometry.canonical(board);
+
+        self.entries
+            .get(&(canonical, player))
+            .map(|moves| {
+                moves
+                    .iter()
+                    .map(|(cell, weight)| (self.geometry.untransform(symmetry, *cell), *weight))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Picks a book move at random in proportion to the weights.
+    pub fn choose<R: Rng>(&self, board: &Bitboard<M>, player: XorZ, rng: &mut R) -> Option<usize> {
+        let moves = self.moves(board, player);
+
+        moves
+            .choose_weighted(rng, |(_, weight)| u64::from(*weight))
+            .ok()
+            .map(|(cell, _)| *cell)
+    }
+}
+
+/// Reading and writing the text format.
+#[cfg(feature = "std")]
+impl<M: Mask> OpeningBook<M> {
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let mut file = fs::File::create(path)?;
+
+        self.write_to(&mut file)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = fs::File::open(path)?;
+
+        Self::read_from(BufReader::new(file))
+    }
+
+    /// Writes the book in its text format, positions in a stable order.
+    pub fn write_to<W: Write>(&self, out: &mut W) -> Result<(), Error> {
+        writeln!(out, "{} {}", self.geometry.size, self.geometry.in_a_row)?;
+
+        let mut entries: Vec<_> = self.entries.iter().collect();
+        entries.sort_by_key(|((board, player), _)| {
+            (
+                board.occupied().count(),
+                board.marks(XorZ::X),
+                board.marks(XorZ::Z),
+                *player as u8,
+            )
+        });
+
+        for ((board, player), moves) in entries {
+            let cells: String = (0..self.geometry.cells())
+                .map(|cell| match board.cell(cell) {
+                    CellState::Empty => '.',
+                    CellState::Marked(XorZ::X) => 'X',
+                    CellState::Marked(XorZ::Z) => '0',
+                    CellState::Blocked => unreachable!("bitboards have no blocked cells"),
+                })
+                .collect();
+
+            write!(out, "{} {}", cells, player_char(*player))?;
+
+            for (cell, weight) in moves {
+                write!(out, " {}:{}", cell, weight)?;
+            }
+
+            writeln!(out)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn read_from<R: BufRead>(input: R) -> Result<Self, Error> {
+        let mut lines = input.lines();
+
+        let header = lines.next().ok_or(BookError::InvalidHeader)??;
+        let numbers: Vec<usize> = header
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map_err(|_| BookError::InvalidHeader)?;
+
+        let (size, in_a_row) = match numbers[..] {
+            [size, in_a_row] if in_a_row > 0 && in_a_row <= size && size * size <= M::BITS => {
+                (size, in_a_row)
+            }
+            _ => return Err(BookError::InvalidHeader.into()),
+        };
+
+        let mut book = Self::new(Geometry::new(size, in_a_row));
+
+        for (number, line) in lines.enumerate() {
+            let line = line?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            // the header is line 1
+            let invalid = BookError::InvalidEntry { line: number + 2 };
+            let (board, player, moves) = book.parse_entry(&line).ok_or(invalid)?;
+
+            for (cell, weight) in moves {
+                book.add(&board, player, cell, weight);
+            }
+        }
+
+        Ok(book)
+    }
+
+    fn parse_entry(&self, line: &str) -> Option<(Bitboard<M>, XorZ, Moves)> {
+        let mut parts = line.split_whitespace();
+        let cells = parts.next()?;
+
+        if cells.chars().count() != self.geometry.cells() {
+            return None;
+        }
+
+        let mut board = Bitboard::new();
+
+        for (cell, c) in cells.chars().enumerate() {
+            match c {
+                'X' => board.place(cell, XorZ::X),
+                '0' => board.place(cell, XorZ::Z),
+                '.' => (),
+                _ => return None,
+            }
+        }
+
+        let player = match parts.next()? {
+            "X" => XorZ::X,
+            "0" => XorZ::Z,
+            _ => return None,
+        };
+
+        let moves = parts
+            .map(|m| {
+                let (cell, weight) = m.split_once(':')?;
+                let cell: usize = cell.parse().ok()?;
+
+                if cell >= self.geometry.cells() || board.cell(cell) != CellState::Empty {
+                    return None;
+                }
+
+                Some((cell, weight.parse().ok()?))
+            })
+            .collect::<Option<_>>()?;
+
+        Some((board, player, moves))
+    }
+}
+
+#[cfg(feature = "std")]
+fn player_char(player: XorZ) -> char {
+    match player {
+        XorZ::X => 'X',
+        XorZ::Z => '0',
+    }
+}
+
+/// Fills an opening book with the moves of the first `plies` plies
+/// of games, X moving first.
+pub struct BookBuilder<M: Mask> {
+    book: OpeningBook<M>,
+    plies: usize,
+}
+
+impl<M: Mask> BookBuilder<M> {
+    pub fn new(geometry: Geometry, plies: usize) -> Self {
+        Self {
+            book: OpeningBook::new(geometry),
+            plies,
+        }
+    }
+
+    /// Plays the engine against itself, starting every game with a
+    /// random move. Moves of the winner and of drawn games are added.
+    pub fn add_self_play(&mut self, engine: &Engine<M>, games: usize, seed: u64) -> &mut Self {
+        let geometry = self.book.geometry;
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        for _ in 0..games {
+            let mut board = Bitboard::new();
+            let mut player = XorZ::X;
+            let mut moves = Vec::new();
+
+            while !engine.is_over(&board) {
+                let cell = if moves.is_empty() {
+                    let empty: Vec<_> = cells(geometry.full::<M>()).collect();
+                    *empty.choose(&mut rng).unwrap()
+                } else {
+                    engine.search(&board, player).unwrap().0
+                };
+
+                moves.push((board, player, cell));
+                board.place(cell, player);
+                player = player.other();
+            }
+
+            let winner = engine.winner(&board);
+
+            for (position, mover, cell) in moves.into_iter().take(self.plies) {
+                match winner {
+                    Some(winner) if winner == mover => {
+                        self.book.add(&position, mover, cell, WIN_WEIGHT)
+                    }
+                    Some(_) => (),
+                    None => self.book.add(&position, mover, cell, DRAW_WEIGHT),
+                }
+            }
+        }
+
+        self
+    }
+
+    /// Adds the engine's best move for every position within the
+    /// first plies, with a weight of 1.
+    pub fn add_search(&mut self, engine: &Engine<M>) -> &mut Self {
+        let geometry = self.book.geometry;
+        let mut positions = vec![Bitboard::new()];
+        let mut player = XorZ::X;
+
+        for _ in 0..self.plies {
+            let mut next_positions = BTreeMap::new();
+
+            for board in &positions {
+                if let Some((cell, _)) = engine.search(board, player) {
+                    self.book.add(board, player, cell, 1);
+                }
+
+                if engine.is_over(board) {
+                    continue;
+                }
+
+                for cell in cells(geometry.full::<M>() & !board.occupied()) {
+                    let mut next = *board;
+                    next.place(cell, player);
+
+                    next_positions.insert(geometry.canonical(&next).0, next);
+                }
+            }
+
+            positions = next_positions.into_values().collect();
+            player = player.other();
+        }
+
+        self
+    }
+
+    pub fn build(self) -> OpeningBook<M> {
+        self.book
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn geometry() -> Geometry {
+        Geometry::new(4, 3)
+    }
+
+    #[test]
+    fn symmetric_positions_share_moves() {
+        let geometry = geometry();
+        let mut book = OpeningBook::<u16>::new(geometry);
+        let mut board = Bitboard::new();
+
+        board.place(geometry.index(0, 0), XorZ::X);
+        book.add(&board, XorZ::Z, geometry.index(1, 1), 3);
+
+        let mut mirrored = Bitboard::new();
+        mirrored.place(geometry.index(3, 0), XorZ::X);
+
+        assert_eq!(
+            book.moves(&mirrored, XorZ::Z),
+            vec![(geometry.index(2, 1), 3)]
+        );
+        assert!(book.moves(&mirrored, XorZ::X).is_empty());
+        assert_eq!(book.len(), 1);
+    }
+
+    #[test]
+    fn weights_saturate() {
+        let geometry = geometry();
+        let mut book = OpeningBook::<u16>::new(geometry);
+        let board = Bitboard::new();
+
+        book.add(&board, XorZ::X, 0, u32::MAX);
+        book.add(&board, XorZ::X, 0, 1);
+        book.add(&board, XorZ::X, 5, u32::MAX);
+
+        assert_eq!(
+            book.moves(&board, XorZ::X),
+            vec![(0, u32::MAX), (5, u32::MAX)]
+        );
+
+        let mut rng = StdRng::seed_from_u64(1);
+        assert!(book.choose(&board, XorZ::X, &mut rng).is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn build_save_and_load() {
+        let engine = Engine::<u16>::new(geometry(), 2).unwrap();
+        let mut builder = BookBuilder::new(geometry(), 2);
+
+        builder.add_search(&engine).add_self_play(&engine, 5, 1);
+
+        let book = builder.build();
+
+        // the empty board and the first moves, up to symmetry
+        assert!(book.len() >= 4, "{}", book.len());
+
+        let mut text = Vec::new();
+        book.write_to(&mut text).unwrap();
+
+        let loaded = OpeningBook::<u16>::read_from(&text[..]).unwrap();
+
+        assert_eq!(loaded, book);
+
+        let mut again = Vec::new();
+        loaded.write_to(&mut again).unwrap();
+
+        assert_eq!(text, again);
+    }
+
+    #[test]
+    fn engine_plays_from_book() {
+        let geometry = geometry();
+        let mut book = OpeningBook::new(geometry);
+
+        // a corner, which the engine would never choose on its own
+        book.add(&Bitboard::new(), XorZ::X, 0, 1);
+
+        let mut engine = Engine::<u16>::new(geometry, 2).unwrap().with_book(book);
+        let corners = [0, 3, 12, 15];
+
+        assert!(corners.contains(&engine.choose_move(&Bitboard::new(), XorZ::X).unwrap()));
+
+        let mut board = Bitboard::new();
+        board.place(0, XorZ::X);
+
+        // out of book
+        assert!(engine.choose_move(&board, XorZ::Z).is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn invalid_books() {
+        let books = [
+            "",
+            "4",
+            "4 5\n",
+            "4 3\nXX X 0:1",
+            "4 3\n................ X 16:1",
+        ];
+
+        for text in books.iter() {
+            assert!(
+                OpeningBook::<u16>::read_from(text.as_bytes()).is_err(),
+                "{:?}",
+                text
+            );
+        }
+    }
+}
@@ -0,0 +1,305 @@
+use alloc::sync::Arc;
+
+use rand::{rngs::StdRng, seq::IteratorRandom, SeedableRng};
+
+use super::clock::{TimeControl, TimeSource};
+use super::*;
+
+/// Sets up games that don't follow the default rules: 0 starting,
+/// alternating starters in a series of games, handicap marks
+/// placed before the first move, blocked cells or a clock.
+#[derive(Debug, Default)]
+pub struct GameBuilder {
+    starting: Option<XorZ>,
+    handicap: Vec<(CellCoord, XorZ)>,
+    blocked: Vec<CellCoord>,
+    /// how many more cells to block at random, and the seed
+    random_blocked: Option<(usize, u64)>,
+    early_draw: EarlyDraw,
+    clock: Option<Clock>,
+}
+
+impl GameBuilder {
+    pub fn new() -> GameBuilder {
+        GameBuilder::default()
+    }
+
+    pub fn with_starting_player(self, starting: XorZ) -> Self {
+        Self {
+            starting: Some(starting),
+            ..self
+        }
+    }
+
+    /// Starting player of game number `index` (counting from 0) in a
+    /// series where `first` starts the first game and the players then
+    /// take turns starting.
+    pub fn with_alternating_start(self, first: XorZ, index: usize) -> Self {
+        let starting = if index.is_multiple_of(2) {
+            first
+        } else {
+            first.other()
+        };
+
+        self.with_starting_player(starting)
+    }
+
+    /// Places a mark for `player` before the game starts.
+    pub fn with_handicap_mark(mut self, x: usize, y: usize, player: XorZ) -> Self {
+        self.handicap.push(((x, y), player));
+        self
+    }
+
+    /// Blocks a cell for the whole game: nobody can mark it, so no
+    /// line through it can be won.
+    pub fn with_blocked_cell(mut self, x: usize, y: usize) -> Self {
+        self.blocked.push((x, y));
+        self
+    }
+
+    /// Blocks `count` cells picked at random from those left empty by
+    /// the other setup options; all of them if there are fewer.
+    pub fn with_random_blocked_cells(self, count: usize, seed: u64) -> Self {
+        Self {
+            random_blocked: Some((count, seed)),
+            ..self
+        }
+    }
+
+    pub fn with_early_draw(self, early_draw: EarlyDraw) -> Self {
+        Self { early_draw, ..self }
+    }
+
+    /// Gives both players a clock; the clock of the starting player
+    /// runs from the moment the game is built.
+    pub fn with_clock(
+        self,
+        control: TimeControl,
+        source: Arc<dyn TimeSource + Send + Sync>,
+    ) -> Self {
+        Self {
+            clock: Some(Clock::new(control, source)),
+            ..self
+        }
+    }
+
+    pub fn build(self) -> Result<Game, SetupError> {
+        let mut board = ClassicBoard::new();
+        let mut blocked = 0;
+
+        for &(x, y) in &self.blocked {
+            check_bounds(x, y)?;
+
+            blocked |= u16::bit(cell_index(x, y));
+        }
+
+        for ((x, y), player) in self.handicap {
+            check_bounds(x, y)?;
+
+            let index = cell_index(x, y);
+
+            if blocked.contains(u16::bit(index)) {
+                return Err(SetupError::CellBlocked { x, y });
+            }
+
+            if board.cell(index) != CellState::Empty {
+                return Err(SetupError::CellMarkedTwice { x, y });
+            }
+
+            board.place(index, player);
+        }
+
+        let completed = [XorZ::X, XorZ::Z]
+            .iter()
+            .any(|xz| board.wins(*xz, &CLASSIC_WIN_MASKS));
+
+        if completed {
+            return Err(SetupError::LineCompleted);
+        }
+
+        if let Some((count, seed)) = self.random_blocked {
+            let empty = CLASSIC_FULL & !board.occupied() & !blocked;
+            let mut rng = StdRng::seed_from_u64(seed);
+
+            for (x, y) in mask_cells(empty).choose_multiple(&mut rng, count) {
+                blocked |= u16::bit(cell_index(x, y));
+            }
+        }
+
+        let game = Game::setup(
+            self.starting.unwrap_or(XorZ::X),
+            board,
+            blocked,
+            self.early_draw,
+            self.clock,
+        );
+
+        Ok(game)
+    }
+}
+
+fn check_bounds(x: usize, y: usize) -> Result<(), SetupError> {
+    if x > MAX_INDEX {
+        return Err(SetupError::new_oob(x));
+    }
+
+    if y > MAX_INDEX {
+        return Err(SetupError::new_oob(y));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use XorZ::*;
+
+    #[test]
+    fn z_starts() {
+        let mut g = GameBuilder::new().with_starting_player(Z).build().unwrap();
+
+        assert_eq!(g.turn(), Z);
+
+        g.mark(1, 1).unwrap();
+
+        assert_eq!(g.turn(), X);
+        assert_eq!(g.turn_number(), 1);
+
+        g.mark(0, 0).unwrap();
+
+        assert_eq!(g.turn(), Z);
+        assert_eq!(g.turn_number(), 2);
+    }
+
+    #[test]
+    fn alternating_start() {
+        let starters: Vec<_> = (0..4)
+            .map(|i| {
+                GameBuilder::new()
+                    .with_alternating_start(Z, i)
+                    .build()
+                    .unwrap()
+                    .starting_player()
+            })
+            .collect();
+
+        assert_eq!(starters, vec![Z, X, Z, X]);
+    }
+
+    #[test]
+    fn handicap_win() {
+        let mut g = GameBuilder::new()
+            .with_handicap_mark(0, 0, Z)
+            .with_handicap_mark(1, 1, Z)
+            .build()
+            .unwrap();
+
+        assert_eq!(g.max_turns(), 4);
+
+        g.mark(1, 0).unwrap();
+
+        // 0 wins with its first mark of the game
+        match g.mark(2, 2).unwrap() {
+            Some(Outcome::Win(Z, WinReason::Lines(lines))) => {
+                assert!(lines.contains(WinLine::DLeft))
+            }
+            o => panic!("{:?} should have been a win for 0", o),
+        }
+    }
+
+    #[test]
+    fn handicap_full_board() {
+        let mut g = GameBuilder::new()
+            .with_handicap_mark(1, 1, Z)
+            .build()
+            .unwrap();
+
+        let moves = [(0, 0), (0, 1), (0, 2), (1, 0), (1, 2), (2, 0), (2, 1)];
+
+        for (x, y) in &moves {
+            assert!(g.mark(*x, *y).unwrap().is_none());
+        }
+
+        // 0 makes the last move now
+        assert_eq!(g.turn(), Z);
+        assert_eq!(g.turn_number(), g.max_turns());
+
+        assert_eq!(
+            g.mark(2, 2).unwrap(),
+            Some(Outcome::Draw(DrawReason::BoardFull))
+        );
+    }
+
+    #[test]
+    fn invalid_handicap() {
+        let builders = vec![
+            GameBuilder::new().with_handicap_mark(3, 0, X),
+            GameBuilder::new()
+                .with_handicap_mark(0, 0, X)
+                .with_handicap_mark(0, 0, Z),
+            GameBuilder::new()
+                .with_handicap_mark(0, 0, Z)
+                .with_handicap_mark(0, 1, Z)
+                .with_handicap_mark(0, 2, Z),
+        ];
+
+        for builder in builders {
+            assert!(builder.build().is_err());
+        }
+    }
+
+    #[test]
+    fn blocked_cells() {
+        let g = GameBuilder::new()
+            .with_blocked_cell(1, 1)
+            .with_handicap_mark(0, 0, Z)
+            .build()
+            .unwrap();
+
+        assert!(g.is_blocked(1, 1));
+        assert_eq!(g.board_state()[1][1], CellState::Blocked);
+        assert_eq!(g.empty_cells().count(), 7);
+        assert_eq!(g.max_turns(), 4);
+
+        let blocked_twice = GameBuilder::new()
+            .with_blocked_cell(1, 1)
+            .with_handicap_mark(1, 1, X)
+            .build();
+
+        assert!(matches!(
+            blocked_twice,
+            Err(SetupError::CellBlocked { x: 1, y: 1 })
+        ));
+        assert!(GameBuilder::new().with_blocked_cell(0, 3).build().is_err());
+    }
+
+    #[test]
+    fn random_blocked_cells() {
+        let blocked = |seed| {
+            GameBuilder::new()
+                .with_handicap_mark(1, 1, X)
+                .with_random_blocked_cells(3, seed)
+                .build()
+                .unwrap()
+                .blocked_cells()
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(blocked(5), blocked(5));
+        assert_eq!(blocked(5).len(), 3);
+        assert!(!blocked(5).contains(&(1, 1)));
+
+        let all = GameBuilder::new()
+            .with_random_blocked_cells(20, 0)
+            .build()
+            .unwrap();
+
+        // nothing left to mark
+        assert_eq!(all.blocked_cells().count(), 9);
+        assert_eq!(
+            all.get_outcome(),
+            Some(Outcome::Draw(DrawReason::BoardFull))
+        );
+    }
+}
@@ -0,0 +1,249 @@
+//! Chess style game clocks.
+use alloc::sync::Arc;
+use core::fmt;
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+use super::XorZ;
+
+/// Tells how much time has passed since some fixed point.
+///
+/// Without `std`, implement it over a hardware timer.
+pub trait TimeSource {
+    fn now(&self) -> Duration;
+}
+
+/// Real, monotonic time.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy)]
+pub struct SystemTime {
+    start: Instant,
+}
+
+#[cfg(feature = "std")]
+impl SystemTime {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for SystemTime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl TimeSource for SystemTime {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// Time that only passes when told to; clones share the same time.
+#[derive(Debug, Clone, Default)]
+pub struct ManualTime {
+    /// nanoseconds
+    now: Arc<AtomicU64>,
+}
+
+impl ManualTime {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.now.fetch_add(by.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
+impl TimeSource for ManualTime {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.now.load(Ordering::Relaxed))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeControl {
+    /// A fixed amount of time for the whole game.
+    SuddenDeath { base: Duration },
+    /// `increment` is added to the player's time after every move.
+    Fischer { base: Duration, increment: Duration },
+    /// The first `delay` of every move does not count.
+    Delay { base: Duration, delay: Duration },
+}
+
+impl TimeControl {
+    fn base(self) -> Duration {
+        match self {
+            TimeControl::SuddenDeath { base }
+            | TimeControl::Fischer { base, .. }
+            | TimeControl::Delay { base, .. } => base,
+        }
+    }
+}
+
+/// Remaining time of both players; only the clock of the player
+/// whose turn it is runs.
+#[derive(Clone)]
+pub struct Clock {
+    control: TimeControl,
+    remaining: [Duration; 2],
+    /// whose clock runs, and since when
+    running: Option<(XorZ, Duration)>,
+    source: Arc<dyn TimeSource + Send + Sync>,
+}
+
+impl Clock {
+    pub fn new(control: TimeControl, source: Arc<dyn TimeSource + Send + Sync>) -> Self {
+        Self {
+            control,
+            remaining: [control.base(); 2],
+            running: None,
+            source,
+        }
+    }
+
+    pub fn control(&self) -> TimeControl {
+        self.control
+    }
+
+    pub(crate) fn source(&self) -> Arc<dyn TimeSource + Send + Sync> {
+        self.source.clone()
+    }
+
+    /// Time left for `player`, including the move in progress.
+    pub fn remaining(&self, player: XorZ) -> Duration {
+        let remaining = self.remaining[player as usize];
+
+        match self.running {
+            Some((running, _)) if running == player => {
+                remaining.checked_sub(self.charged()).unwrap_or_default()
+            }
+            _ => remaining,
+        }
+    }
+
+    /// The player whose time ran out, if any.
+    pub fn flagged(&self) -> Option<XorZ> {
+        let (player, _) = self.running?;
+
+        if self.charged() >= self.remaining[player as usize] {
+            Some(player)
+        } else {
+            None
+        }
+    }
+
+    /// Starts counting the time of `player`'s move.
+    pub(crate) fn start(&mut self, player: XorZ) {
+        self.running = Some((player, self.source.now()));
+    }
+
+    /// Ends the running move, charging its time to the player.
+    pub(crate) fn stop(&mut self) {
+        if let Some((player, _)) = self.running {
+            let remaining = self.remaining(player);
+
+            self.remaining[player as usize] = match self.control {
+                TimeControl::Fischer { increment, .. } => remaining + increment,
+                _ => remaining,
+            };
+
+            self.running = None;
+        }
+    }
+
+    /// Time of the running move that counts against the player.
+    fn charged(&self) -> Duration {
+        let elapsed = match self.running {
+            Some((_, since)) => self.source.now().checked_sub(since).unwrap_or_default(),
+            None => Duration::default(),
+        };
+
+        match self.control {
+            TimeControl::Delay { delay, .. } => elapsed.checked_sub(delay).unwrap_or_default(),
+            _ => elapsed,
+        }
+    }
+}
+
+impl fmt::Debug for Clock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Clock")
+            .field("control", &self.control)
+            .field("remaining", &self.remaining)
+            .field("running", &self.running)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    fn clock(control: TimeControl) -> (Clock, ManualTime) {
+        let time = ManualTime::new();
+
+        (Clock::new(control, Arc::new(time.clone())), time)
+    }
+
+    #[test]
+    fn sudden_death() {
+        let (mut clock, time) = clock(TimeControl::SuddenDeath { base: secs(10) });
+
+        clock.start(XorZ::X);
+        time.advance(secs(4));
+
+        assert_eq!(clock.remaining(XorZ::X), secs(6));
+        assert_eq!(clock.remaining(XorZ::Z), secs(10));
+
+        clock.stop();
+        clock.start(XorZ::Z);
+        time.advance(secs(10));
+
+        assert_eq!(clock.flagged(), Some(XorZ::Z));
+    }
+
+    #[test]
+    fn fischer() {
+        let (mut clock, time) = clock(TimeControl::Fischer {
+            base: secs(10),
+            increment: secs(2),
+        });
+
+        clock.start(XorZ::X);
+        time.advance(secs(1));
+        clock.stop();
+
+        assert_eq!(clock.remaining(XorZ::X), secs(11));
+    }
+
+    #[test]
+    fn delay() {
+        let (mut clock, time) = clock(TimeControl::Delay {
+            base: secs(10),
+            delay: secs(3),
+        });
+
+        clock.start(XorZ::X);
+        time.advance(secs(2));
+
+        assert_eq!(clock.remaining(XorZ::X), secs(10));
+
+        time.advance(secs(3));
+        clock.stop();
+
+        assert_eq!(clock.remaining(XorZ::X), secs(8));
+        assert_eq!(clock.flagged(), None);
+    }
+}
@@ -0,0 +1,169 @@
+//! Plays every possible game through `Game::mark` and compares the
+//! game with a naive implementation of the rules after every move.
+use super::*;
+
+/// The cells of every line, written out by hand.
+const LINES: [(WinLine, [CellCoord; ROW_SIZE]); 8] = [
+    (WinLine::HTop, [(0, 0), (1, 0), (2, 0)]),
+    (WinLine::HMid, [(0, 1), (1, 1), (2, 1)]),
+    (WinLine::HBottom, [(0, 2), (1, 2), (2, 2)]),
+    (WinLine::VLeft, [(0, 0), (0, 1), (0, 2)]),
+    (WinLine::VMid, [(1, 0), (1, 1), (1, 2)]),
+    (WinLine::VRight, [(2, 0), (2, 1), (2, 2)]),
+    (WinLine::DLeft, [(0, 0), (1, 1), (2, 2)]),
+    (WinLine::DRight, [(2, 0), (1, 1), (0, 2)]),
+];
+
+/// The rules, as plainly as possible: no bitboards, no shortcuts.
+#[derive(Debug, Clone, Copy, Default)]
+struct Oracle {
+    /// indexed by x, then y
+    cells: [[Option<XorZ>; ROW_SIZE]; ROW_SIZE],
+}
+
+impl Oracle {
+    fn lines_of(&self, player: XorZ) -> Vec<WinLine> {
+        LINES
+            .iter()
+            .filter(|(_, cells)| cells.iter().all(|&(x, y)| self.cells[x][y] == Some(player)))
+            .map(|(line, _)| *line)
+            .collect()
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        let x_lines = self.lines_of(XorZ::X);
+        let z_lines = self.lines_of(XorZ::Z);
+
+        assert!(x_lines.is_empty() || z_lines.is_empty(), "both players won");
+
+        for (player, lines) in [(XorZ::X, x_lines), (XorZ::Z, z_lines)] {
+            if !lines.is_empty() {
+                let lines = lines.into_iter().collect();
+
+                return Some(Outcome::Win(player, WinReason::Lines(lines)));
+            }
+        }
+
+        let full = self.cells.iter().flatten().all(Option::is_some);
+
+        if full {
+            Some(Outcome::Draw(DrawReason::BoardFull))
+        } else {
+            None
+        }
+    }
+
+    fn board_state(&self) -> BoardState {
+        let mut state = [[CellState::Empty; ROW_SIZE]; ROW_SIZE];
+
+        for (column, marks) in state.iter_mut().zip(&self.cells) {
+            for (cell, mark) in column.iter_mut().zip(marks) {
+                if let Some(player) = mark {
+                    *cell = CellState::Marked(*player);
+                }
+            }
+        }
+
+        state
+    }
+}
+
+/// Finished games by result.
+#[derive(Debug, Default, PartialEq)]
+struct Results {
+    x_wins: u32,
+    z_wins: u32,
+    draws: u32,
+}
+
+/// Tries every cell in `game`, comparing each legal move with the
+/// oracle and going on until every game has ended.
+fn play_all(game: &mut Game, oracle: Oracle, moves: &mut Vec<CellCoord>, results: &mut Results) {
+    for x in 0..ROW_SIZE {
+        for y in 0..ROW_SIZE {
+            if oracle.cells[x][y].is_some() {
+                assert_eq!(
+                    game.mark(x, y),
+                    Err(MarkError::CellMarked),
+                    "{:?} {:?}",
+                    moves,
+                    (x, y)
+                );
+                continue;
+            }
+
+            let player = game.turn();
+            let mut next = game.clone();
+            let mut next_oracle = oracle;
+            next_oracle.cells[x][y] = Some(player);
+            moves.push((x, y));
+
+            let expected = next_oracle.outcome();
+
+            assert_eq!(next.mark(x, y), Ok(expected), "{:?}", moves);
+            assert_eq!(next.get_outcome(), expected, "{:?}", moves);
+            assert_eq!(
+                *next.board_state(),
+                next_oracle.board_state(),
+                "{:?}",
+                moves
+            );
+
+            match expected {
+                None => {
+                    assert_eq!(next.turn(), player.other(), "{:?}", moves);
+
+                    play_all(&mut next, next_oracle, moves, results);
+                }
+                Some(outcome) => {
+                    assert!(next.ended());
+                    assert_eq!(next.mark(0, 0), Err(MarkError::GameEnded));
+
+                    match outcome.winner() {
+                        Some(XorZ::X) => results.x_wins += 1,
+                        Some(XorZ::Z) => results.z_wins += 1,
+                        None => results.draws += 1,
+                    }
+                }
+            }
+
+            moves.pop();
+        }
+    }
+}
+
+fn play_every_game(starting: XorZ) -> Results {
+    let mut game = GameBuilder::new()
+        .with_starting_player(starting)
+        .build()
+        .unwrap();
+    let mut results = Results::default();
+
+    play_all(&mut game, Oracle::default(), &mut Vec::new(), &mut results);
+
+    results
+}
+
+#[test]
+fn every_game_x_starting() {
+    assert_eq!(
+        play_every_game(XorZ::X),
+        Results {
+            x_wins: 131_184,
+            z_wins: 77_904,
+            draws: 46_080,
+        }
+    );
+}
+
+#[test]
+fn every_game_z_starting() {
+    assert_eq!(
+        play_every_game(XorZ::Z),
+        Results {
+            x_wins: 77_904,
+            z_wins: 131_184,
+            draws: 46_080,
+        }
+    );
+}
@@ -0,0 +1,368 @@
+//! Depth limited alpha-beta search for boards of any `Geometry`,
+//! playing from an opening book while it has moves.
+//!
+//! Boards are limited by the widest `Mask`, `U256`, so gomoku is
+//! played on boards of up to 16x16 cells, the usual 15x15 included.
+use alloc::vec;
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use super::bitboard::{cells, Bitboard, Geometry, Mask};
+use super::book::OpeningBook;
+use super::player::{MoveChoice, Player};
+use super::*;
+
+/// Score of a win on the move; wins further away score less.
+const WIN: i32 = 1_000_000;
+
+pub struct Engine<M: Mask> {
+    geometry: Geometry,
+    win_masks: Vec<M>,
+    full: M,
+    /// plies searched before the position is evaluated
+    depth: u32,
+    book: Option<OpeningBook<M>>,
+    rng: StdRng,
+}
+
+impl<M: Mask> Engine<M> {
+    /// Fails if the board has more cells than `M` has bits.
+    pub fn new(geometry: Geometry, depth: u32) -> Result<Self, EngineError> {
+        if geometry.cells() > M::BITS {
+            return Err(EngineError::BoardTooLarge {
+                cells: geometry.cells(),
+                bits: M::BITS,
+            });
+        }
+
+        Ok(Self {
+            geometry,
+            win_masks: geometry.win_masks(),
+            full: geometry.full(),
+            depth,
+            book: None,
+            rng: StdRng::seed_from_u64(0),
+        })
+    }
+
+    /// Plays from `book` before searching.
+    pub fn with_book(self, book: OpeningBook<M>) -> Self {
+        assert_eq!(book.geometry(), self.geometry, "book is for another board");
+
+        Self {
+            book: Some(book),
+            ..self
+        }
+    }
+
+    /// Seeds the choice between weighted book moves.
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            ..self
+        }
+    }
+
+    pub fn geometry(&self) -> Geometry {
+        self.geometry
+    }
+
+    pub fn winner(&self, board: &Bitboard<M>) -> Option<XorZ> {
+        [XorZ::X, XorZ::Z]
+            .iter()
+            .copied()
+            .find(|player| board.wins(*player, &self.win_masks))
+    }
+
+    /// Has someone won or is the board full?
+    pub fn is_over(&self, board: &Bitboard<M>) -> bool {
+        self.is_over_blocked(board, M::default())
+    }
+
+    /// A book move if there is one, otherwise the best move found by
+    /// searching. `None` if the game is over.
+    pub fn choose_move(&mut self, board: &Bitboard<M>, player: XorZ) -> Option<usize> {
+        if self.is_over(board) {
+            return None;
+        }
+
+        let book_move = match &self.book {
+            Some(book) => book.choose(board, player, &mut self.rng),
+            None => None,
+        };
+
+        book_move.or_else(|| self.search(board, player).map(|(cell, _)| cell))
+    }
+
+    /// Best move for `player` and its score; positive scores are
+    /// good for `player`.
+    pub fn search(&self, board: &Bitboard<M>, player: XorZ) -> Option<(usize, i32)> {
+        self.search_blocked(board, M::default(), player)
+    }
+
+    /// Like `search`, on a board where nobody can mark the cells of
+    /// `blocked`.
+    pub fn search_blocked(
+        &self,
+        board: &Bitboard<M>,
+        blocked: M,
+        player: XorZ,
+    ) -> Option<(usize, i32)> {
+        if self.is_over_blocked(board, blocked) {
+            return None;
+        }
+
+        let mut best = None;
+        let mut alpha = -WIN - 1;
+
+        for cell in self.candidates(board, blocked) {
+            let mut next = *board;
+            next.place(cell, player);
+
+            let score = -self.negamax(&next, blocked, player.other(), self.depth, -WIN - 1, -alpha);
+
+            if score > alpha {
+                alpha = score;
+                best = Some((cell, score));
+            }
+        }
+
+        best
+    }
+
+    fn is_over_blocked(&self, board: &Bitboard<M>, blocked: M) -> bool {
+        self.winner(board).is_some() || board.occupied() | blocked == self.full
+    }
+
+    fn negamax(
+        &self,
+        board: &Bitboard<M>,
+        blocked: M,
+        player: XorZ,
+        depth: u32,
+        mut alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        // only the player who just moved can have completed a line
+        if board.wins(player.other(), &self.win_masks) {
+            let ply = (self.depth - depth + 1) as i32;
+
+            return -(WIN - ply);
+        }
+
+        if board.occupied() | blocked == self.full {
+            return 0;
+        }
+
+        if depth == 0 {
+            return self.evaluate(board, blocked, player);
+        }
+
+        for cell in self.candidates(board, blocked) {
+            let mut next = *board;
+            next.place(cell, player);
+
+            let score = -self.negamax(&next, blocked, player.other(), depth - 1, -beta, -alpha);
+
+            if score > alpha {
+                alpha = score;
+            }
+
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        alpha
+    }
+
+    /// Lines still open to only one player count for that player,
+    /// more so the more marks they already hold.
+    fn evaluate(&self, board: &Bitboard<M>, blocked: M, player: XorZ) -> i32 {
+        let (mine, theirs) = (board.marks(player), board.marks(player.other()));
+
+        self.win_masks
+            .iter()
+            .filter(|mask| (blocked & **mask).is_empty())
+            .map(|mask| {
+                let (m, t) = ((mine & *mask).count(), (theirs & *mask).count());
+
+                match (m, t) {
+                    (0, 0) => 0,
+                    (m, 0) => 1 << (2 * m),
+                    (0, t) => -(1 << (2 * t)),
+                    _ => 0,
+                }
+            })
+            .sum()
+    }
+
+    /// Empty cells next to a mark, or the center of an empty board;
+    /// every empty cell if none of those can be marked.
+    fn candidates(&self, board: &Bitboard<M>, blocked: M) -> Vec<usize> {
+        let occupied = board.occupied();
+        let empty = self.full & !occupied & !blocked;
+
+        if occupied.is_empty() {
+            let middle = self.geometry.size / 2;
+            let center = self.geometry.index(middle, middle);
+
+            if empty.contains(M::bit(center)) {
+                return vec![center];
+            }
+        }
+
+        let size = self.geometry.size as isize;
+
+        let near: Vec<usize> = cells(empty)
+            .filter(|cell| {
+                let (x, y) = self.geometry.coord(*cell);
+
+                (-1..=1).any(|dx| {
+                    (-1..=1).any(|dy| {
+                        let (nx, ny) = (x as isize + dx, y as isize + dy);
+
+                        nx >= 0
+                            && ny >= 0
+                            && nx < size
+                            && ny < size
+                            && occupied
+                                .contains(M::bit(self.geometry.index(nx as usize, ny as usize)))
+                    })
+                })
+            })
+            .collect();
+
+        if near.is_empty() {
+            cells(empty).collect()
+        } else {
+            near
+        }
+    }
+}
+
+/// Plays `Game`s; the engine must be for `Geometry::CLASSIC`.
+impl Player for Engine<u16> {
+    fn choose_move(&mut self, game: &Game) -> MoveChoice {
+        if self.geometry != Geometry::CLASSIC {
+            let Geometry { size, in_a_row } = self.geometry;
+
+            return Err(PlayerError::UnsupportedBoard { size, in_a_row }.into());
+        }
+
+        if game.ended() {
+            return Ok(None);
+        }
+
+        // the book does not know about blocked cells
+        let cell = if game.blocked == 0 {
+            Engine::choose_move(self, &game.board, game.turn())
+        } else {
+            self.search_blocked(&game.board, game.blocked, game.turn())
+                .map(|(cell, _)| cell)
+        };
+
+        Ok(cell.map(cell_coord))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bitboard::U256;
+    use crate::player::{GameDriver, RandomPlayer};
+
+    #[test]
+    fn wins_and_blocks() {
+        let geometry = Geometry::new(5, 4);
+        let engine = Engine::<u32>::new(geometry, 2).unwrap();
+        let mut board = Bitboard::new();
+
+        for x in 0..3 {
+            board.place(geometry.index(x, 0), XorZ::X);
+            board.place(geometry.index(x, 4), XorZ::Z);
+        }
+
+        // X completes its row rather than block 0's
+        assert_eq!(
+            engine.search(&board, XorZ::X).unwrap().0,
+            geometry.index(3, 0)
+        );
+        assert_eq!(
+            engine.search(&board, XorZ::Z).unwrap().0,
+            geometry.index(3, 4)
+        );
+
+        board.place(geometry.index(3, 4), XorZ::X);
+
+        // 0 can only stop X by taking the other end
+        assert_eq!(
+            engine.search(&board, XorZ::Z).unwrap().0,
+            geometry.index(3, 0)
+        );
+    }
+
+    #[test]
+    fn gomoku() {
+        let geometry = Geometry::new(15, 5);
+
+        assert_eq!(
+            Engine::<u128>::new(geometry, 1).err(),
+            Some(EngineError::BoardTooLarge {
+                cells: 225,
+                bits: 128
+            })
+        );
+
+        let engine = Engine::<U256>::new(geometry, 2).unwrap();
+        let mut board = Bitboard::new();
+
+        for y in 11..15 {
+            board.place(geometry.index(14, y), XorZ::X);
+            board.place(geometry.index(0, y), XorZ::Z);
+        }
+
+        // the only free end of the column, past the first 128 bits
+        assert_eq!(
+            engine.search(&board, XorZ::X).unwrap().0,
+            geometry.index(14, 10)
+        );
+    }
+
+    #[test]
+    fn never_loses_the_classic_game() {
+        for seed in 0..10 {
+            let engine = Engine::<u16>::new(Geometry::CLASSIC, 9).unwrap();
+            let mut driver = GameDriver::new(Game::new(), RandomPlayer::new(seed), engine);
+
+            assert_ne!(driver.run().unwrap().winner(), Some(XorZ::X));
+        }
+    }
+
+    #[test]
+    fn plays_around_blocked_cells() {
+        for seed in 0..20 {
+            let game = GameBuilder::new()
+                .with_random_blocked_cells(3, seed)
+                .build()
+                .unwrap();
+            let engine = Engine::<u16>::new(Geometry::CLASSIC, 9).unwrap();
+            let mut driver = GameDriver::new(game, engine, RandomPlayer::new(seed));
+
+            assert!(driver.run().is_ok());
+        }
+    }
+
+    #[test]
+    fn plays_only_classic_games() {
+        let mut engine = Engine::<u16>::new(Geometry::new(4, 3), 2).unwrap();
+
+        assert!(matches!(
+            Player::choose_move(&mut engine, &Game::new()),
+            Err(Error::Player(PlayerError::UnsupportedBoard {
+                size: 4,
+                in_a_row: 3
+            }))
+        ));
+    }
+}
@@ -1,17 +1,106 @@
-use failure::{Error, Fail};
+use alloc::string::{String, ToString};
+use core::fmt;
 
-use super::Outcome;
+use super::{Outcome, XorZ};
 
-#[derive(Debug, Fail)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum MarkError {
-    #[fail(display = "Cell index {} out of bounds; max index is {}", index, max)]
     OutOfBounds { index: usize, max: usize },
-
-    #[fail(display = "Cell is already marked!")]
     CellMarked,
+    CellBlocked,
+    GameEnded,
+}
 
-    #[fail(display = "Game already finished!")]
+#[derive(Debug)]
+pub enum ActionError {
     GameEnded,
+    NoDrawOffer,
+}
+
+#[derive(Debug)]
+pub enum SetupError {
+    OutOfBounds { index: usize, max: usize },
+    CellMarkedTwice { x: usize, y: usize },
+    CellBlocked { x: usize, y: usize },
+    LineCompleted,
+    InvalidPosition,
+}
+
+#[derive(Debug)]
+pub enum MemoryError {
+    InvalidMatchbox { line: usize },
+}
+
+#[derive(Debug)]
+pub enum BookError {
+    InvalidHeader,
+    InvalidEntry { line: usize },
+}
+
+#[derive(Debug)]
+pub enum PlayerError {
+    NoMoveReady { player: XorZ },
+    UnsupportedBoard { size: usize, in_a_row: usize },
+}
+
+#[derive(Debug)]
+pub enum RecordError {
+    Syntax {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    IllegalMove {
+        line: usize,
+        column: usize,
+        reason: String,
+    },
+    UnsupportedTag {
+        line: usize,
+        column: usize,
+        name: String,
+        value: String,
+    },
+    ResultMismatch {
+        line: usize,
+        column: usize,
+    },
+}
+
+#[derive(Debug)]
+pub enum MatchError {
+    GameInProgress,
+    MatchDecided,
+    NoGames,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TrainError {
+    NoEvaluationInterval,
+    NoEvaluationGames,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum EngineError {
+    BoardTooLarge { cells: usize, bits: usize },
+}
+
+/// Any of the errors above, for operations that can fail in more
+/// than one way.
+#[derive(Debug)]
+pub enum Error {
+    Mark(MarkError),
+    Action(ActionError),
+    Setup(SetupError),
+    Memory(MemoryError),
+    Book(BookError),
+    Player(PlayerError),
+    Record(RecordError),
+    Match(MatchError),
+    Train(TrainError),
+    Engine(EngineError),
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
 }
 
 impl MarkError {
@@ -23,4 +112,233 @@ impl MarkError {
     }
 }
 
-pub type MarkResult = Result<Option<Outcome>, Error>;
+impl SetupError {
+    pub(super) fn new_oob(index: usize) -> Self {
+        SetupError::OutOfBounds {
+            index,
+            max: super::MAX_INDEX,
+        }
+    }
+}
+
+impl RecordError {
+    pub(super) fn syntax(line: usize, column: usize, message: &str) -> Self {
+        RecordError::Syntax {
+            line,
+            column,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for MarkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MarkError::OutOfBounds { index, max } => write!(
+                f,
+                "Cell index {} out of bounds; max index is {}",
+                index, max
+            ),
+            MarkError::CellMarked => write!(f, "Cell is already marked!"),
+            MarkError::CellBlocked => write!(f, "Cell is blocked!"),
+            MarkError::GameEnded => write!(f, "Game already finished!"),
+        }
+    }
+}
+
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ActionError::GameEnded => write!(f, "Game already finished!"),
+            ActionError::NoDrawOffer => write!(f, "There is no draw offer to accept!"),
+        }
+    }
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SetupError::OutOfBounds { index, max } => write!(
+                f,
+                "Cell index {} out of bounds; max index is {}",
+                index, max
+            ),
+            SetupError::CellMarkedTwice { x, y } => {
+                write!(f, "Cell {} {} is marked more than once!", x, y)
+            }
+            SetupError::CellBlocked { x, y } => write!(f, "Cell {} {} is blocked!", x, y),
+            SetupError::LineCompleted => write!(f, "Handicap marks already complete a line!"),
+            SetupError::InvalidPosition => write!(f, "Invalid position notation!"),
+        }
+    }
+}
+
+impl fmt::Display for MemoryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MemoryError::InvalidMatchbox { line } => {
+                write!(f, "Invalid matchbox on line {}", line)
+            }
+        }
+    }
+}
+
+impl fmt::Display for BookError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BookError::InvalidHeader => write!(f, "Invalid opening book header"),
+            BookError::InvalidEntry { line } => {
+                write!(f, "Invalid opening book entry on line {}", line)
+            }
+        }
+    }
+}
+
+impl fmt::Display for PlayerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlayerError::NoMoveReady { player } => write!(f, "{:?} has no move ready", player),
+            PlayerError::UnsupportedBoard { size, in_a_row } => write!(
+                f,
+                "Player is for {}x{} boards with {} in a row, not the classic board",
+                size, size, in_a_row
+            ),
+        }
+    }
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecordError::Syntax {
+                line,
+                column,
+                message,
+            } => write!(f, "Line {} column {}: {}", line, column, message),
+            RecordError::IllegalMove {
+                line,
+                column,
+                reason,
+            } => write!(
+                f,
+                "Line {} column {}: illegal move: {}",
+                line, column, reason
+            ),
+            RecordError::UnsupportedTag {
+                line,
+                column,
+                name,
+                value,
+            } => write!(
+                f,
+                "Line {} column {}: unsupported {} \"{}\"",
+                line, column, name, value
+            ),
+            RecordError::ResultMismatch { line, column } => write!(
+                f,
+                "Line {} column {}: result does not match the game",
+                line, column
+            ),
+        }
+    }
+}
+
+impl fmt::Display for MatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MatchError::GameInProgress => write!(f, "The current game has not finished yet!"),
+            MatchError::MatchDecided => write!(f, "Match already decided!"),
+            MatchError::NoGames => write!(f, "Matches must be played to at least 1 game"),
+        }
+    }
+}
+
+impl fmt::Display for TrainError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TrainError::NoEvaluationInterval => {
+                write!(f, "Evaluations must be at least 1 episode apart")
+            }
+            TrainError::NoEvaluationGames => write!(f, "Evaluations must play at least 1 game"),
+        }
+    }
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EngineError::BoardTooLarge { cells, bits } => write!(
+                f,
+                "Board of {} cells does not fit a mask of {} bits",
+                cells, bits
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Mark(e) => e.fmt(f),
+            Error::Action(e) => e.fmt(f),
+            Error::Setup(e) => e.fmt(f),
+            Error::Memory(e) => e.fmt(f),
+            Error::Book(e) => e.fmt(f),
+            Error::Player(e) => e.fmt(f),
+            Error::Record(e) => e.fmt(f),
+            Error::Match(e) => e.fmt(f),
+            Error::Train(e) => e.fmt(f),
+            Error::Engine(e) => e.fmt(f),
+            #[cfg(feature = "std")]
+            Error::Io(e) => e.fmt(f),
+        }
+    }
+}
+
+macro_rules! from_errors {
+    ($($variant:ident($error:ty)),*) => {
+        $(
+            impl From<$error> for Error {
+                fn from(error: $error) -> Self {
+                    Error::$variant(error)
+                }
+            }
+        )*
+    };
+}
+
+from_errors!(
+    Mark(MarkError),
+    Action(ActionError),
+    Setup(SetupError),
+    Memory(MemoryError),
+    Book(BookError),
+    Player(PlayerError),
+    Record(RecordError),
+    Match(MatchError),
+    Train(TrainError),
+    Engine(EngineError)
+);
+
+#[cfg(feature = "std")]
+from_errors!(Io(std::io::Error));
+
+#[cfg(feature = "std")]
+mod std_errors {
+    use super::*;
+
+    impl std::error::Error for MarkError {}
+    impl std::error::Error for ActionError {}
+    impl std::error::Error for SetupError {}
+    impl std::error::Error for MemoryError {}
+    impl std::error::Error for BookError {}
+    impl std::error::Error for PlayerError {}
+    impl std::error::Error for RecordError {}
+    impl std::error::Error for MatchError {}
+    impl std::error::Error for TrainError {}
+    impl std::error::Error for EngineError {}
+
+    impl std::error::Error for Error {}
+}
+
+pub type MarkResult = Result<Option<Outcome>, MarkError>;
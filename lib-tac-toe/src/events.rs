@@ -0,0 +1,68 @@
+//! Notifications about what happens in a `Game`.
+use alloc::{boxed::Box, vec::Vec};
+use core::fmt;
+
+use super::errors::MarkError;
+use super::{CellCoord, Outcome, XorZ};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    MarkPlaced { coord: CellCoord, player: XorZ },
+    TurnChanged(XorZ),
+    DrawOffered(XorZ),
+    GameEnded(Outcome),
+    MoveRejected { coord: CellCoord, error: MarkError },
+}
+
+type Observer = Box<dyn FnMut(&GameEvent) + Send>;
+
+/// Identifies an observer, so it can be removed again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ObserverId(usize);
+
+/// Callbacks registered on a game.
+///
+/// Observers belong to the game they were registered on and are
+/// not copied when the game is cloned, e.g. by the solver.
+#[derive(Default)]
+pub(crate) struct Observers {
+    next_id: usize,
+    list: Vec<(ObserverId, Observer)>,
+}
+
+impl Observers {
+    pub(crate) fn add(&mut self, observer: Observer) -> ObserverId {
+        let id = ObserverId(self.next_id);
+
+        self.next_id += 1;
+        self.list.push((id, observer));
+
+        id
+    }
+
+    pub(crate) fn remove(&mut self, id: ObserverId) -> bool {
+        let count = self.list.len();
+
+        self.list.retain(|(observer_id, _)| *observer_id != id);
+
+        self.list.len() != count
+    }
+
+    pub(crate) fn notify(&mut self, event: GameEvent) {
+        for (_, observer) in self.list.iter_mut() {
+            observer(&event);
+        }
+    }
+}
+
+impl Clone for Observers {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl fmt::Debug for Observers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} observers", self.list.len())
+    }
+}
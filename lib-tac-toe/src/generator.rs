@@ -0,0 +1,235 @@
+//! Random legal games and positions, repeatable from a seed, e.g.
+//! for property tests and training data.
+use alloc::vec::Vec;
+
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use super::*;
+
+/// Most moves a game on the classic board can last.
+const MAX_MOVES: u8 = 9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutcomeClass {
+    Ongoing,
+    Won,
+    Drawn,
+}
+
+impl OutcomeClass {
+    pub fn of(game: &Game) -> Self {
+        match game.get_outcome() {
+            None => OutcomeClass::Ongoing,
+            Some(Outcome::Win(..)) => OutcomeClass::Won,
+            Some(Outcome::Draw(_)) => OutcomeClass::Drawn,
+        }
+    }
+}
+
+/// A generated game and the moves that led to it.
+#[derive(Debug, Clone)]
+pub struct RandomGame {
+    pub moves: Vec<CellCoord>,
+    pub game: Game,
+}
+
+/// Plays random legal games that meet all the given constraints.
+///
+/// The number of moves is picked first, then moves are picked at
+/// random, backtracking whenever the game can no longer meet the
+/// constraints.
+pub struct GameGenerator {
+    rng: StdRng,
+    starting: XorZ,
+    min_moves: u8,
+    max_moves: u8,
+    to_move: Option<XorZ>,
+    outcome: Option<OutcomeClass>,
+}
+
+impl GameGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            starting: XorZ::X,
+            min_moves: 0,
+            max_moves: MAX_MOVES,
+            to_move: None,
+            outcome: None,
+        }
+    }
+
+    pub fn with_starting_player(self, starting: XorZ) -> Self {
+        Self { starting, ..self }
+    }
+
+    /// Games last from `min` to `max` moves, both included.
+    pub fn with_moves(self, min: u8, max: u8) -> Self {
+        Self {
+            min_moves: min,
+            max_moves: max.min(MAX_MOVES),
+            ..self
+        }
+    }
+
+    /// The player whose turn it would be after the last move, even
+    /// if the game has ended.
+    pub fn with_side_to_move(self, player: XorZ) -> Self {
+        Self {
+            to_move: Some(player),
+            ..self
+        }
+    }
+
+    pub fn with_outcome(self, outcome: OutcomeClass) -> Self {
+        Self {
+            outcome: Some(outcome),
+            ..self
+        }
+    }
+
+    /// The next random game; `None` if no game meets the constraints.
+    pub fn generate(&mut self) -> Option<RandomGame> {
+        let starting = self.starting;
+        let to_move = self.to_move;
+
+        let mut lengths: Vec<u8> = (self.min_moves..=self.max_moves)
+            .filter(|&moves| {
+                let next = if moves.is_multiple_of(2) {
+                    starting
+                } else {
+                    starting.other()
+                };
+
+                to_move.is_none_or(|player| player == next)
+            })
+            .collect();
+
+        lengths.shuffle(&mut self.rng);
+
+        let start = GameBuilder::new()
+            .with_starting_player(starting)
+            .build()
+            .unwrap();
+
+        for length in lengths {
+            let mut moves = Vec::new();
+
+            if let Some(game) = self.search(&start, length, &mut moves) {
+                return Some(RandomGame { moves, game });
+            }
+        }
+
+        None
+    }
+
+    /// Plays `left` more random moves, returning the final game if it
+    /// has the wanted outcome.
+    fn search(&mut self, game: &Game, left: u8, moves: &mut Vec<CellCoord>) -> Option<Game> {
+        if left == 0 {
+            let wanted = self.outcome.is_none_or(|o| o == OutcomeClass::of(game));
+
+            return if wanted { Some(game.clone()) } else { None };
+        }
+
+        let mut cells: Vec<_> = game.legal_moves().collect();
+        cells.shuffle(&mut self.rng);
+
+        for (x, y) in cells {
+            let mut next = game.clone();
+            next.mark(x, y).unwrap();
+            moves.push((x, y));
+
+            if let Some(found) = self.search(&next, left - 1, moves) {
+                return Some(found);
+            }
+
+            moves.pop();
+        }
+
+        None
+    }
+}
+
+impl Iterator for GameGenerator {
+    type Item = RandomGame;
+
+    fn next(&mut self) -> Option<RandomGame> {
+        self.generate()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn repeatable() {
+        let first: Vec<_> = GameGenerator::new(3).take(20).map(|g| g.moves).collect();
+        let second: Vec<_> = GameGenerator::new(3).take(20).map(|g| g.moves).collect();
+        let other: Vec<_> = GameGenerator::new(4).take(20).map(|g| g.moves).collect();
+
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn constraints() {
+        let classes = [
+            OutcomeClass::Ongoing,
+            OutcomeClass::Won,
+            OutcomeClass::Drawn,
+        ];
+
+        for &outcome in classes.iter() {
+            let generator = GameGenerator::new(1)
+                .with_starting_player(XorZ::Z)
+                .with_moves(4, 9)
+                .with_side_to_move(XorZ::X)
+                .with_outcome(outcome);
+
+            for random in generator.take(30) {
+                let game = random.game;
+                let moves = random.moves.len();
+
+                assert_eq!(OutcomeClass::of(&game), outcome);
+                assert!((4..=9).contains(&moves));
+                // Z started, so X would move after an odd number of moves
+                assert_eq!(moves % 2, 1);
+                assert_eq!(game.starting_player(), XorZ::Z);
+
+                if outcome == OutcomeClass::Ongoing {
+                    assert_eq!(game.turn(), XorZ::X);
+                }
+
+                // the moves replay to the same game
+                let mut replayed = GameBuilder::new()
+                    .with_starting_player(XorZ::Z)
+                    .build()
+                    .unwrap();
+
+                for &(x, y) in &random.moves {
+                    replayed.mark(x, y).unwrap();
+                }
+
+                assert_eq!(replayed.board_state(), game.board_state());
+            }
+        }
+    }
+
+    #[test]
+    fn impossible() {
+        let mut generator = GameGenerator::new(0)
+            .with_moves(0, 4)
+            .with_outcome(OutcomeClass::Won);
+
+        assert!(generator.generate().is_none());
+
+        // the board is full after 9 moves, so Z would be next
+        let mut generator = GameGenerator::new(0)
+            .with_moves(9, 9)
+            .with_side_to_move(XorZ::X);
+
+        assert!(generator.next().is_none());
+    }
+}
@@ -1,29 +1,132 @@
-use failure::{bail, ensure, Error};
-
+//! Core logic of playing a tic-tac-toe game.
+//!
+//! Without the default `std` feature the crate is `no_std` and only
+//! needs an allocator; file I/O, wall clock time, training and
+//! tournaments are left out.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+pub mod analysis;
+pub mod bitboard;
+pub mod book;
+mod builder;
+pub mod clock;
+#[cfg(test)]
+mod conformance;
+pub mod engine;
 pub mod errors;
-
+pub mod events;
+pub mod generator;
+pub mod menace;
+pub mod player;
+pub mod puzzle;
+pub mod record;
+pub mod series;
+pub mod solver;
+pub mod stats;
+pub mod symmetry;
+pub mod tablebase;
+#[cfg(feature = "std")]
+pub mod tournament;
+#[cfg(feature = "std")]
+pub mod train;
+
+use alloc::{boxed::Box, vec::Vec};
+
+use bitboard::{Bitboard, Mask, CLASSIC_FULL, CLASSIC_WIN_MASKS};
+pub use builder::GameBuilder;
+use clock::Clock;
 use errors::*;
+use events::*;
 
 const ROW_SIZE: usize = 3;
 const MAX_INDEX: usize = ROW_SIZE - 1;
-const MAX_TURNS: u8 = 5;
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum XorZ {
     X,
     Z,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+impl XorZ {
+    /// The opponent.
+    pub fn other(self) -> Self {
+        match self {
+            XorZ::X => XorZ::Z,
+            XorZ::Z => XorZ::X,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum CellState {
     Empty,
     Marked(XorZ),
+    /// nobody can mark the cell, so lines through it cannot be won
+    Blocked,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Outcome {
-    Draw,
-    Win(XorZ, WinLine),
+    Draw(DrawReason),
+    Win(XorZ, WinReason),
+}
+
+impl Outcome {
+    pub fn winner(self) -> Option<XorZ> {
+        match self {
+            Outcome::Draw(_) => None,
+            Outcome::Win(winner, _) => Some(winner),
+        }
+    }
+
+    /// The completed lines, if the game was won on the board.
+    pub fn win_lines(self) -> Option<WinLines> {
+        match self {
+            Outcome::Win(_, WinReason::Lines(lines)) => Some(lines),
+            _ => None,
+        }
+    }
+}
+
+/// Why the winner won.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WinReason {
+    /// The winner completed one or more lines.
+    Lines(WinLines),
+    /// The opponent resigned.
+    Resignation,
+    /// The opponent ran out of time.
+    Timeout,
+    /// The opponent left the game, e.g. by closing the connection.
+    Disconnect,
+}
+
+/// Which rule ended the game in a draw.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DrawReason {
+    /// All cells have been marked.
+    BoardFull,
+    /// Every line contains both symbols, so nobody can win anymore.
+    DeadPosition,
+    /// Nobody can force a win, assuming perfect play from both sides.
+    ForcedDraw,
+    /// A draw offer was accepted.
+    Agreement,
+}
+
+/// Controls how early a game is declared a draw.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum EarlyDraw {
+    /// Only a full board is a draw.
+    #[default]
+    Off,
+    /// Also end the game as soon as no line can still be won.
+    DeadPosition,
+    /// Also end the game as soon as the solver proves that the
+    /// game will be drawn under perfect play.
+    ForcedDraw,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -38,62 +141,367 @@ pub enum WinLine {
     HBottom,
 }
 
+impl WinLine {
+    pub const ALL: [WinLine; 8] = [
+        WinLine::DLeft,
+        WinLine::DRight,
+        WinLine::VLeft,
+        WinLine::VMid,
+        WinLine::VRight,
+        WinLine::HTop,
+        WinLine::HMid,
+        WinLine::HBottom,
+    ];
+
+    /// Coordinates of the cells that make up the line.
+    pub fn cells(self) -> [CellCoord; ROW_SIZE] {
+        use WinLine::*;
+
+        match self {
+            DLeft => [(0, 0), (1, 1), (2, 2)],
+            DRight => [(2, 0), (1, 1), (0, 2)],
+            VLeft => [(0, 0), (0, 1), (0, 2)],
+            VMid => [(1, 0), (1, 1), (1, 2)],
+            VRight => [(2, 0), (2, 1), (2, 2)],
+            HTop => [(0, 0), (1, 0), (2, 0)],
+            HMid => [(0, 1), (1, 1), (2, 1)],
+            HBottom => [(0, 2), (1, 2), (2, 2)],
+        }
+    }
+
+    /// Bits of the line's cells on a classic `Bitboard`.
+    pub fn mask(self) -> u16 {
+        CLASSIC_WIN_MASKS[self as usize]
+    }
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// Set of lines completed by the winning move.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct WinLines(u8);
+
+impl WinLines {
+    pub fn contains(self, line: WinLine) -> bool {
+        self.0 & line.bit() != 0
+    }
+
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = WinLine> {
+        WinLine::ALL
+            .iter()
+            .copied()
+            .filter(move |l| self.contains(*l))
+    }
+
+    fn insert(&mut self, line: WinLine) {
+        self.0 |= line.bit();
+    }
+}
+
+impl From<WinLine> for WinLines {
+    fn from(line: WinLine) -> Self {
+        let mut lines = Self::default();
+        lines.insert(line);
+        lines
+    }
+}
+
+impl core::iter::FromIterator<WinLine> for WinLines {
+    fn from_iter<I: IntoIterator<Item = WinLine>>(iter: I) -> Self {
+        let mut lines = Self::default();
+
+        for line in iter {
+            lines.insert(line);
+        }
+
+        lines
+    }
+}
+
 pub type CellCoord = (usize, usize);
 pub type BoardState = [[CellState; ROW_SIZE]; ROW_SIZE];
 
+/// The bitboard backing a `Game`.
+pub type ClassicBoard = Bitboard<u16>;
+
+impl ClassicBoard {
+    pub fn from_board_state(state: &BoardState) -> Self {
+        let mut board = Self::new();
+
+        for (x, column) in state.iter().enumerate() {
+            for (y, cell) in column.iter().enumerate() {
+                if let CellState::Marked(xz) = cell {
+                    board.place(cell_index(x, y), *xz);
+                }
+            }
+        }
+
+        board
+    }
+
+    pub fn board_state(&self) -> BoardState {
+        let mut state = [[CellState::Empty; ROW_SIZE]; ROW_SIZE];
+
+        for (x, column) in state.iter_mut().enumerate() {
+            for (y, cell) in column.iter_mut().enumerate() {
+                *cell = self.cell(cell_index(x, y));
+            }
+        }
+
+        state
+    }
+}
+
+fn cell_index(x: usize, y: usize) -> usize {
+    y * ROW_SIZE + x
+}
+
+fn cell_coord(index: usize) -> CellCoord {
+    (index % ROW_SIZE, index / ROW_SIZE)
+}
+
+/// Coordinates of the cells whose bits are set in `mask`.
+fn mask_cells(mask: u16) -> impl Iterator<Item = CellCoord> {
+    bitboard::cells(mask).map(cell_coord)
+}
+
+/// A line that a player can complete with one more mark.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Threat {
+    pub line: WinLine,
+    /// the empty cell that completes the line
+    pub cell: CellCoord,
+}
+
+#[derive(Debug, Clone)]
 pub struct Game {
     turn_number: u8,
+    /// the last turn in which the starting player can mark a cell
+    max_turns: u8,
     /// who's turn is it?
     turn_of: XorZ,
+    /// who marked the first cell?
+    starting: XorZ,
+    /// number of cells marked before the game started, by X and 0
+    handicap: [u8; 2],
+    /// cells that cannot be marked
+    blocked: u16,
+    empty_count: u8,
+    board: ClassicBoard,
+    /// `board` and `blocked` as a grid, for `board_state`
     state: BoardState,
     outcome: Option<Outcome>,
+    early_draw: EarlyDraw,
+    clock: Option<Clock>,
+    /// player who offered a draw that has not been answered yet
+    draw_offer: Option<XorZ>,
+    observers: Observers,
 }
 
 impl Game {
     /// Creates a new game with the default starting state.
     pub fn new() -> Self {
-        Game {
+        Self::with_early_draw(EarlyDraw::Off)
+    }
+
+    /// Creates a new game that may end in a draw before
+    /// the board is full.
+    pub fn with_early_draw(early_draw: EarlyDraw) -> Self {
+        Self::setup(XorZ::X, ClassicBoard::new(), 0, early_draw, None)
+    }
+
+    /// Starts a game from an already validated position.
+    fn setup(
+        starting: XorZ,
+        board: ClassicBoard,
+        blocked: u16,
+        early_draw: EarlyDraw,
+        clock: Option<Clock>,
+    ) -> Self {
+        let handicap = [
+            board.marks(XorZ::X).count() as u8,
+            board.marks(XorZ::Z).count() as u8,
+        ];
+        let empty_count = (CLASSIC_FULL & !board.occupied() & !blocked).count() as u8;
+
+        let mut state = board.board_state();
+        for (x, y) in mask_cells(blocked) {
+            state[x][y] = CellState::Blocked;
+        }
+
+        let mut game = Game {
             turn_number: 1,
-            turn_of: XorZ::X,
-            state: [[CellState::Empty; ROW_SIZE]; ROW_SIZE],
+            // the starting player marks every other empty cell
+            max_turns: empty_count.div_ceil(2),
+            turn_of: starting,
+            starting,
+            handicap,
+            blocked,
+            empty_count,
+            board,
+            state,
             outcome: None,
+            early_draw,
+            clock,
+            draw_offer: None,
+            observers: Observers::default(),
+        };
+
+        if empty_count == 0 {
+            game.outcome = Some(Outcome::Draw(DrawReason::BoardFull));
+        } else if let Some(clock) = &mut game.clock {
+            clock.start(starting);
         }
+
+        game
     }
 
     /// Places the next X or 0 on the board.
+    ///
+    /// If the player's time has already run out, no mark is placed
+    /// and the game is lost on time.
     pub fn mark(&mut self, x: usize, y: usize) -> MarkResult {
-        if self.outcome.is_some() {
-            bail!(MarkError::GameEnded);
+        if let Some(outcome) = self.check_time() {
+            return Ok(Some(outcome));
         }
 
-        Self::check_index_bounds(x, y)?;
+        if let Err(error) = self.check_markable(x, y) {
+            self.observers.notify(GameEvent::MoveRejected {
+                coord: (x, y),
+                error: error.clone(),
+            });
 
-        let cell = &mut self.state[x][y];
+            return Err(error);
+        }
 
-        if let CellState::Marked(_) = cell {
-            bail!(MarkError::CellMarked);
-        };
+        self.board.place(cell_index(x, y), self.turn_of);
+        self.state[x][y] = CellState::Marked(self.turn_of);
+        self.empty_count -= 1;
+
+        // making a move declines the opponent's draw offer
+        if self.draw_offer == Some(self.turn_of.other()) {
+            self.draw_offer = None;
+        }
+
+        self.observers.notify(GameEvent::MarkPlaced {
+            coord: (x, y),
+            player: self.turn_of,
+        });
 
-        *cell = CellState::Marked(self.turn_of);
+        if let Some(clock) = &mut self.clock {
+            clock.stop();
+        }
 
         let outcome = self.check_outcome(x, y);
-        if outcome.is_some() {
-            return Ok(outcome);
+        if let Some(outcome) = outcome {
+            self.observers.notify(GameEvent::GameEnded(outcome));
+
+            return Ok(Some(outcome));
         }
 
         self.advance_turn();
 
+        if let Some(clock) = &mut self.clock {
+            clock.start(self.turn_of);
+        }
+
+        self.observers.notify(GameEvent::TurnChanged(self.turn_of));
+
         Ok(None)
     }
 
+    /// Ends the game if the player whose turn it is ran out of time.
+    ///
+    /// Games with a clock should call this regularly while waiting
+    /// for a move; returns the outcome if the game just ended.
+    pub fn check_time(&mut self) -> Option<Outcome> {
+        if self.ended() {
+            return None;
+        }
+
+        let loser = self.clock.as_ref()?.flagged()?;
+
+        Some(self.end(Outcome::Win(loser.other(), WinReason::Timeout)))
+    }
+
+    /// `player` gives up; the opponent wins.
+    pub fn resign(&mut self, player: XorZ) -> Result<Outcome, ActionError> {
+        self.check_running()?;
+
+        Ok(self.end(Outcome::Win(player.other(), WinReason::Resignation)))
+    }
+
+    /// `player` left the game; the opponent wins.
+    pub fn disconnect(&mut self, player: XorZ) -> Result<Outcome, ActionError> {
+        self.check_running()?;
+
+        Ok(self.end(Outcome::Win(player.other(), WinReason::Disconnect)))
+    }
+
+    /// Offers the opponent a draw, which stands until the opponent
+    /// accepts it or makes a move.
+    pub fn offer_draw(&mut self, player: XorZ) -> Result<(), ActionError> {
+        self.check_running()?;
+
+        self.draw_offer = Some(player);
+        self.observers.notify(GameEvent::DrawOffered(player));
+
+        Ok(())
+    }
+
+    /// Accepts the draw offered by the opponent of `player`.
+    pub fn accept_draw(&mut self, player: XorZ) -> Result<Outcome, ActionError> {
+        self.check_running()?;
+
+        if self.draw_offer != Some(player.other()) {
+            return Err(ActionError::NoDrawOffer);
+        }
+
+        Ok(self.end(Outcome::Draw(DrawReason::Agreement)))
+    }
+
+    /// Player whose draw offer is waiting for an answer.
+    pub fn draw_offer(&self) -> Option<XorZ> {
+        self.draw_offer
+    }
+
+    pub fn clock(&self) -> Option<&Clock> {
+        self.clock.as_ref()
+    }
+
+    /// Registers a callback for everything that happens in this game.
+    pub fn subscribe<F: FnMut(&GameEvent) + Send + 'static>(&mut self, observer: F) -> ObserverId {
+        self.observers.add(Box::new(observer))
+    }
+
+    /// Removes an observer; returns false if it was not registered.
+    pub fn unsubscribe(&mut self, id: ObserverId) -> bool {
+        self.observers.remove(id)
+    }
+
     pub fn turn(&self) -> XorZ {
         self.turn_of
     }
 
+    /// The board as a grid of cells, blocked cells included.
     pub fn board_state(&self) -> &BoardState {
         &self.state
     }
 
+    pub fn bitboard(&self) -> &ClassicBoard {
+        &self.board
+    }
+
     pub fn get_outcome(&self) -> Option<Outcome> {
         self.outcome
     }
@@ -106,115 +514,238 @@ impl Game {
         if Self::check_index_bounds(x, y).is_err() {
             false
         } else {
-            self.state[x][y] != CellState::Empty
+            self.board.cell(cell_index(x, y)) != CellState::Empty
+        }
+    }
+
+    pub fn is_blocked(&self, x: usize, y: usize) -> bool {
+        Self::check_index_bounds(x, y).is_ok() && self.blocked.contains(u16::bit(cell_index(x, y)))
+    }
+
+    /// Cells that can be marked by the player whose turn it is.
+    pub fn legal_moves(&self) -> impl Iterator<Item = CellCoord> + '_ {
+        let ended = self.ended();
+
+        self.empty_cells().filter(move |_| !ended)
+    }
+
+    pub fn empty_cells(&self) -> impl Iterator<Item = CellCoord> {
+        mask_cells(self.empty_mask(&self.board))
+    }
+
+    pub fn blocked_cells(&self) -> impl Iterator<Item = CellCoord> {
+        mask_cells(self.blocked)
+    }
+
+    pub fn marks_of(&self, player: XorZ) -> impl Iterator<Item = CellCoord> {
+        mask_cells(self.board.marks(player))
+    }
+
+    /// Lines in which `player` has two marks and the third cell is empty.
+    pub fn threats_for(&self, player: XorZ) -> Vec<Threat> {
+        self.threats_on(&self.board, player)
+    }
+
+    /// Empty cells that would give `player` two threats at once.
+    pub fn forks_for(&self, player: XorZ) -> Vec<CellCoord> {
+        self.empty_cells()
+            .filter(|&(x, y)| {
+                let mut board = self.board;
+                board.place(cell_index(x, y), player);
+
+                let threats = self.threats_on(&board, player);
+
+                // two threats that share their empty cell can be blocked at once
+                threats
+                    .iter()
+                    .any(|t1| threats.iter().any(|t2| t1.cell != t2.cell))
+            })
+            .collect()
+    }
+
+    pub fn early_draw(&self) -> EarlyDraw {
+        self.early_draw
+    }
+
+    /// Player that marked the first cell after any handicap marks.
+    pub fn starting_player(&self) -> XorZ {
+        self.starting
+    }
+
+    /// A turn is complete once both players have marked a cell.
+    pub fn turn_number(&self) -> u8 {
+        self.turn_number
+    }
+
+    /// Number of turns in a game that ends with a full board.
+    pub fn max_turns(&self) -> u8 {
+        self.max_turns
+    }
+
+    /// A copy of the game without a clock, that will not invoke the
+    /// solver on its own.
+    fn searchable(&self) -> Game {
+        let mut game = self.clone();
+        game.clock = None;
+
+        if game.early_draw == EarlyDraw::ForcedDraw {
+            game.early_draw = EarlyDraw::DeadPosition;
+        }
+
+        game
+    }
+
+    fn check_running(&self) -> Result<(), ActionError> {
+        if self.ended() {
+            Err(ActionError::GameEnded)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Ends the game without a move being made.
+    fn end(&mut self, outcome: Outcome) -> Outcome {
+        if let Some(clock) = &mut self.clock {
+            clock.stop();
         }
+
+        self.outcome = Some(outcome);
+        self.draw_offer = None;
+        self.observers.notify(GameEvent::GameEnded(outcome));
+
+        outcome
     }
 
     fn check_outcome(&mut self, last_x: usize, last_y: usize) -> Option<Outcome> {
-        // there cannot be a winner before turn 3
-        if (self.turn_number as usize) < ROW_SIZE {
-            return None;
+        let marks = self.turn_number + self.handicap[self.turn_of as usize];
+
+        // there cannot be a winner with less than 3 marks
+        if (marks as usize) >= ROW_SIZE {
+            let lines = self.win_lines(last_x, last_y);
+
+            if !lines.is_empty() {
+                self.outcome = Some(Outcome::Win(self.turn_of, WinReason::Lines(lines)));
+                return self.outcome;
+            }
         }
 
-        if let Some(wl) = self.win_line(last_x, last_y) {
-            self.outcome = Some(Outcome::Win(self.turn_of, wl));
+        if self.empty_count == 0 {
+            self.outcome = Some(Outcome::Draw(DrawReason::BoardFull));
             return self.outcome;
         }
 
-        if self.turn_number == MAX_TURNS {
-            self.outcome = Some(Outcome::Draw);
-            return self.outcome;
+        if let Some(reason) = self.early_draw_reason() {
+            self.outcome = Some(Outcome::Draw(reason));
         }
 
-        None
+        self.outcome
     }
 
-    fn win_line(&self, x: usize, y: usize) -> Option<WinLine> {
-        if self.won_v(x, y) {
-            match x {
-                0 => Some(WinLine::VLeft),
-                1 => Some(WinLine::VMid),
-                2 => Some(WinLine::VRight),
-                _ => unreachable!(),
+    fn early_draw_reason(&self) -> Option<DrawReason> {
+        match self.early_draw {
+            EarlyDraw::Off => None,
+            EarlyDraw::DeadPosition => {
+                if self.dead_position() {
+                    Some(DrawReason::DeadPosition)
+                } else {
+                    None
+                }
             }
-        } else if self.won_h(x, y) {
-            match y {
-                0 => Some(WinLine::HTop),
-                1 => Some(WinLine::HMid),
-                2 => Some(WinLine::HBottom),
-                _ => unreachable!(),
+            EarlyDraw::ForcedDraw => {
+                if self.dead_position() {
+                    return Some(DrawReason::DeadPosition);
+                }
+
+                // the solver needs to see the position with
+                // the other player to move
+                let mut next = self.clone();
+                next.advance_turn();
+
+                if solver::value(&next) == solver::Value::Draw {
+                    Some(DrawReason::ForcedDraw)
+                } else {
+                    None
+                }
             }
-        } else if self.won_d_left() {
-            Some(WinLine::DLeft)
-        } else if self.won_d_right() {
-            Some(WinLine::DRight)
-        } else {
-            None
         }
     }
 
-    /// Vertical.
-    fn won_v(&self, x: usize, y: usize) -> bool {
-        let cell1 = self.state[x][y];
+    /// Cells of `board` that are neither marked nor blocked.
+    fn empty_mask(&self, board: &ClassicBoard) -> u16 {
+        CLASSIC_FULL & !board.occupied() & !self.blocked
+    }
 
-        let (y2, y3) = Self::other_two_indexes(y);
+    fn threats_on(&self, board: &ClassicBoard, player: XorZ) -> Vec<Threat> {
+        let marks = board.marks(player);
+        let empty = self.empty_mask(board);
 
-        let cell2 = self.state[x][y2];
-        let cell3 = self.state[x][y3];
+        WinLine::ALL
+            .iter()
+            .filter(|line| (marks & line.mask()).count() == 2)
+            .filter_map(|line| {
+                let cell = mask_cells(empty & line.mask()).next()?;
 
-        Self::marked_same(cell1, cell2, cell3)
+                Some(Threat { line: *line, cell })
+            })
+            .collect()
     }
 
-    /// Horizontal
-    fn won_h(&self, x: usize, y: usize) -> bool {
-        let cell1 = self.state[x][y];
+    /// Does every line contain both an X and a 0, or a blocked cell?
+    fn dead_position(&self) -> bool {
+        let (x, z) = (self.board.marks(XorZ::X), self.board.marks(XorZ::Z));
 
-        let (x2, x3) = Self::other_two_indexes(x);
+        CLASSIC_WIN_MASKS.iter().all(|mask| {
+            !(self.blocked & mask).is_empty() || (!(x & mask).is_empty() && !(z & mask).is_empty())
+        })
+    }
 
-        let cell2 = self.state[x2][y];
-        let cell3 = self.state[x3][y];
+    /// All lines through `x`, `y` marked by the same player.
+    fn win_lines(&self, x: usize, y: usize) -> WinLines {
+        let last = u16::bit(cell_index(x, y));
+        let marks = self.board.marks(self.turn_of);
 
-        Self::marked_same(cell1, cell2, cell3)
+        WinLine::ALL
+            .iter()
+            .copied()
+            .filter(|line| line.mask().contains(last) && marks.contains(line.mask()))
+            .collect()
     }
 
-    /// Diagonal 0 0, 1 1, 2 2
-    fn won_d_left(&self) -> bool {
-        Self::marked_same(self.state[0][0], self.state[1][1], self.state[2][2])
-    }
+    fn advance_turn(&mut self) {
+        self.turn_of = self.turn_of.other();
 
-    /// Diagonal 2 0, 1 1, 0 2
-    fn won_d_right(&self) -> bool {
-        Self::marked_same(self.state[2][0], self.state[1][1], self.state[0][2])
+        if self.turn_of == self.starting {
+            self.turn_number += 1;
+        }
     }
 
-    fn other_two_indexes(i: usize) -> CellCoord {
-        match i {
-            0 => (1, 2),
-            1 => (0, 2),
-            2 => (0, 1),
-            _ => unreachable!(),
+    fn check_markable(&self, x: usize, y: usize) -> Result<(), MarkError> {
+        if self.outcome.is_some() {
+            return Err(MarkError::GameEnded);
         }
-    }
 
-    fn marked_same(cell1: CellState, cell2: CellState, cell3: CellState) -> bool {
-        cell1 == cell2 && cell2 == cell3
+        Self::check_index_bounds(x, y)?;
+
+        if self.is_blocked(x, y) {
+            return Err(MarkError::CellBlocked);
+        }
+
+        if let CellState::Marked(_) = self.board.cell(cell_index(x, y)) {
+            return Err(MarkError::CellMarked);
+        };
+
+        Ok(())
     }
 
-    fn advance_turn(&mut self) {
-        use XorZ::*;
+    fn check_index_bounds(x: usize, y: usize) -> Result<(), MarkError> {
+        if x > MAX_INDEX {
+            return Err(MarkError::new_oob(x));
+        }
 
-        match self.turn_of {
-            X => self.turn_of = Z,
-            Z => {
-                self.turn_of = X;
-                self.turn_number += 1;
-            }
+        if y > MAX_INDEX {
+            return Err(MarkError::new_oob(y));
         }
-    }
 
-    fn check_index_bounds(x: usize, y: usize) -> Result<(), Error> {
-        ensure!(x <= MAX_INDEX, MarkError::new_oob(x));
-        ensure!(y <= MAX_INDEX, MarkError::new_oob(y));
         Ok(())
     }
 }
@@ -225,11 +756,19 @@ impl Default for Game {
     }
 }
 
+// games must be able to move into other threads and async tasks
+const _: fn() = || {
+    fn assert_send<T: Send>() {}
+
+    assert_send::<Game>();
+};
+
 #[cfg(test)]
 mod test {
     use super::*;
     use Outcome::*;
     use WinLine::*;
+    use WinReason::*;
     use XorZ::*;
 
     // TODO: find a way to check that the returned errors have the correct types
@@ -253,6 +792,37 @@ mod test {
         assert!(g.mark(0, 0).is_err());
     }
 
+    #[test]
+    fn blocked_cells() {
+        let mut g = GameBuilder::new().with_blocked_cell(2, 2).build().unwrap();
+
+        assert_eq!(g.mark(2, 2), Err(MarkError::CellBlocked));
+        assert!(!g.legal_moves().any(|c| c == (2, 2)));
+
+        for (x, y) in &[(0, 0), (1, 0), (1, 1), (2, 1)] {
+            assert!(g.mark(*x, *y).unwrap().is_none());
+        }
+
+        // X would threaten DLeft if not for the blocked cell
+        assert!(g.threats_for(X).is_empty());
+    }
+
+    #[test]
+    fn blocked_dead_position() {
+        let mut g = GameBuilder::new()
+            .with_blocked_cell(1, 1)
+            .with_early_draw(EarlyDraw::DeadPosition)
+            .build()
+            .unwrap();
+
+        for (x, y) in &[(0, 0), (2, 0), (2, 2)] {
+            assert!(g.mark(*x, *y).unwrap().is_none());
+        }
+
+        // every line through the center is blocked, the others have both
+        assert_outcome(g.mark(0, 2), Draw(DrawReason::DeadPosition));
+    }
+
     #[test]
     fn horizontal_win() {
         // as X
@@ -313,6 +883,24 @@ mod test {
         assert_winner(g.mark(0, 2), Z, DRight);
     }
 
+    #[test]
+    fn double_win() {
+        let mut g = game_with_markings(&[
+            (1, 0),
+            (0, 1),
+            (2, 0),
+            (0, 2),
+            (1, 1),
+            (2, 1),
+            (2, 2),
+            (1, 2),
+        ]);
+
+        let lines = [HTop, DLeft].iter().copied().collect();
+
+        assert_outcome(g.mark(0, 0), Win(X, Lines(lines)));
+    }
+
     #[test]
     fn marking_finished() {
         let mut g = game_with_markings(&[(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)]);
@@ -333,7 +921,27 @@ mod test {
             (2, 2),
         ]);
 
-        assert_outcome(g.mark(2, 1), Draw);
+        assert_outcome(g.mark(2, 1), Draw(DrawReason::BoardFull));
+    }
+
+    #[test]
+    fn dead_position_draw() {
+        let mut g = Game::with_early_draw(EarlyDraw::DeadPosition);
+
+        for (x, y) in &[(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (2, 0), (2, 1)] {
+            assert!(g.mark(*x, *y).unwrap().is_none());
+        }
+
+        // the only empty cell left, (1, 2), cannot complete any line
+        assert_outcome(g.mark(2, 2), Draw(DrawReason::DeadPosition));
+    }
+
+    #[test]
+    fn forced_draw() {
+        let mut g = Game::with_early_draw(EarlyDraw::ForcedDraw);
+
+        // the empty board is a draw, and so is every first move
+        assert_outcome(g.mark(1, 1), Draw(DrawReason::ForcedDraw));
     }
 
     #[test]
@@ -374,7 +982,7 @@ mod test {
 
         assert_turn(&g, 5, X);
 
-        assert_outcome(g.mark(2, 1), Draw);
+        assert_outcome(g.mark(2, 1), Draw(DrawReason::BoardFull));
 
         // last move should not change the turn because
         // there are no more turns after it
@@ -410,6 +1018,178 @@ mod test {
         assert!(g.is_marked(0, 0));
     }
 
+    #[test]
+    fn legal_moves() {
+        let mut g = game_with_markings(&[(0, 0), (1, 1)]);
+
+        let moves: Vec<_> = g.legal_moves().collect();
+
+        assert_eq!(moves.len(), 7);
+        assert!(!moves.contains(&(0, 0)));
+        assert!(!moves.contains(&(1, 1)));
+
+        assert_eq!(g.marks_of(X).collect::<Vec<_>>(), vec![(0, 0)]);
+        assert_eq!(g.marks_of(Z).collect::<Vec<_>>(), vec![(1, 1)]);
+
+        g = game_with_markings(&[(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)]);
+
+        // a finished game has empty cells, but no legal moves
+        assert_eq!(g.empty_cells().count(), 4);
+        assert_eq!(g.legal_moves().count(), 0);
+    }
+
+    #[test]
+    fn threats() {
+        let g = game_with_markings(&[(0, 0), (1, 1), (2, 0), (1, 0)]);
+
+        assert!(g.threats_for(X).is_empty());
+
+        assert_eq!(
+            g.threats_for(Z),
+            vec![Threat {
+                line: VMid,
+                cell: (1, 2)
+            }]
+        );
+    }
+
+    #[test]
+    fn forks() {
+        let g = game_with_markings(&[(0, 0), (1, 1), (2, 2), (0, 2)]);
+
+        assert_eq!(g.forks_for(X), vec![(2, 0)]);
+
+        let g = game_with_markings(&[(1, 1), (0, 0)]);
+
+        assert!(g.forks_for(X).is_empty());
+    }
+
+    #[test]
+    fn board_state_view() {
+        let g = game_with_markings(&[(2, 0), (0, 1)]);
+        let state = g.board_state();
+
+        assert_eq!(state[2][0], CellState::Marked(X));
+        assert_eq!(state[0][1], CellState::Marked(Z));
+        assert_eq!(state[1][1], CellState::Empty);
+
+        assert_eq!(ClassicBoard::from_board_state(state), *g.bitboard());
+    }
+
+    #[test]
+    fn events() {
+        use std::sync::{Arc, Mutex};
+
+        let mut g = game_with_markings(&[(0, 0), (1, 0), (0, 1), (1, 1)]);
+        let events = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = events.clone();
+        let id = g.subscribe(move |e| recorded.lock().unwrap().push(e.clone()));
+
+        assert!(g.mark(1, 1).is_err());
+        g.mark(2, 2).unwrap();
+        g.mark(1, 2).unwrap();
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                GameEvent::MoveRejected {
+                    coord: (1, 1),
+                    error: errors::MarkError::CellMarked
+                },
+                GameEvent::MarkPlaced {
+                    coord: (2, 2),
+                    player: X
+                },
+                GameEvent::TurnChanged(Z),
+                GameEvent::MarkPlaced {
+                    coord: (1, 2),
+                    player: Z
+                },
+                GameEvent::GameEnded(Win(Z, Lines(VMid.into()))),
+            ]
+        );
+
+        // clones don't notify the original observers
+        let mut clone = game_with_markings(&[(0, 0)]);
+        clone.subscribe(|_| panic!("observer of the original game"));
+        clone.clone().mark(1, 1).unwrap();
+
+        assert!(g.unsubscribe(id));
+        assert!(!g.unsubscribe(id));
+    }
+
+    #[test]
+    fn resignation_and_disconnect() {
+        let mut g = game_with_markings(&[(1, 1)]);
+
+        assert_eq!(g.resign(Z).unwrap(), Win(X, Resignation));
+        assert!(g.resign(X).is_err());
+        assert!(g.mark(0, 0).is_err());
+
+        let mut g = Game::new();
+
+        assert_eq!(g.disconnect(X).unwrap(), Win(Z, Disconnect));
+    }
+
+    #[test]
+    fn draw_offers() {
+        let mut g = Game::new();
+
+        // nothing to accept yet, and nobody can accept their own offer
+        assert!(g.accept_draw(Z).is_err());
+
+        g.offer_draw(X).unwrap();
+        assert!(g.accept_draw(X).is_err());
+
+        // the offer stands while X makes its move...
+        g.mark(1, 1).unwrap();
+        assert_eq!(g.draw_offer(), Some(X));
+
+        // ...and is declined by 0 making one
+        g.mark(0, 0).unwrap();
+        assert_eq!(g.draw_offer(), None);
+        assert!(g.accept_draw(Z).is_err());
+
+        g.offer_draw(Z).unwrap();
+
+        assert_eq!(g.accept_draw(X).unwrap(), Draw(DrawReason::Agreement));
+        assert!(g.offer_draw(X).is_err());
+    }
+
+    #[test]
+    fn lost_on_time() {
+        use clock::{ManualTime, TimeControl};
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        let time = ManualTime::new();
+        let mut g = GameBuilder::new()
+            .with_clock(
+                TimeControl::Fischer {
+                    base: Duration::from_secs(5),
+                    increment: Duration::from_secs(1),
+                },
+                Arc::new(time.clone()),
+            )
+            .build()
+            .unwrap();
+
+        time.advance(Duration::from_secs(3));
+        g.mark(1, 1).unwrap();
+
+        assert_eq!(g.check_time(), None);
+        assert_eq!(g.clock().unwrap().remaining(X), Duration::from_secs(3));
+
+        time.advance(Duration::from_secs(5));
+
+        // 0 gets no chance to mark once its time has run out
+        assert_outcome(g.mark(0, 0), Win(X, Timeout));
+        assert!(!g.is_marked(0, 0));
+        assert_eq!(g.get_outcome().unwrap().winner(), Some(X));
+        assert!(g.mark(0, 0).is_err());
+    }
+
     fn game_with_markings(m: &[CellCoord]) -> Game {
         let mut g = Game::new();
 
@@ -421,7 +1201,7 @@ mod test {
     }
 
     fn assert_winner(r: MarkResult, xz: XorZ, line: WinLine) {
-        assert_outcome(r, Win(xz, line));
+        assert_outcome(r, Win(xz, Lines(line.into())));
     }
 
     fn assert_outcome(r: MarkResult, o: Outcome) {
@@ -0,0 +1,404 @@
+//! A learning opponent modelled on Donald Michie's MENACE: a
+//! matchbox for every position, holding beads for each possible
+//! move. Moves are drawn at random in proportion to the beads,
+//! which are added after wins and draws and taken away after losses.
+//!
+//! Symmetric positions share a matchbox, so games must start with X.
+//! Blocked cells are part of the position, and get no beads.
+use alloc::collections::BTreeMap;
+#[cfg(feature = "std")]
+use std::fs;
+#[cfg(feature = "std")]
+use std::io::{BufRead, BufReader, Write};
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::*;
+
+/// Beads per move in a new matchbox, by the number of marks
+/// already on the board.
+const INITIAL_BEADS: [u32; 9] = [4, 4, 3, 3, 2, 2, 1, 1, 1];
+
+const WIN_BEADS: u32 = 3;
+const DRAW_BEADS: u32 = 1;
+const LOSS_BEADS: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Matchbox {
+    /// beads for each cell of the canonical position
+    beads: [u32; 9],
+}
+
+impl Matchbox {
+    fn new(x: u16, z: u16, blocked: u16) -> Self {
+        let marks = (x | z).count_ones() as usize;
+        let mut beads = [0; 9];
+
+        for (cell, count) in beads.iter_mut().enumerate() {
+            if (x | z | blocked) & 1 << cell == 0 {
+                *count = INITIAL_BEADS[marks];
+            }
+        }
+
+        Self { beads }
+    }
+}
+
+pub struct Menace {
+    /// matchboxes by `box_key`
+    boxes: BTreeMap<usize, Matchbox>,
+    /// matchbox and canonical cell of every move in the current game
+    moves: Vec<(usize, usize)>,
+    rng: StdRng,
+}
+
+impl Menace {
+    #[cfg(feature = "std")]
+    pub fn new() -> Self {
+        Self::with_rng(StdRng::from_entropy())
+    }
+
+    /// A MENACE whose random draws are repeatable.
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_rng(StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(rng: StdRng) -> Self {
+        Self {
+            boxes: BTreeMap::new(),
+            moves: Vec::new(),
+            rng,
+        }
+    }
+
+    /// Draws a bead from the matchbox of the game's position and
+    /// remembers it for `learn`.
+    ///
+    /// Returns `None` if the game has ended.
+    pub fn choose_move(&mut self, game: &Game) -> Option<CellCoord> {
+        if game.ended() {
+            return None;
+        }
+
+        let (index, symmetry) = self.open_box(game);
+        let beads = self.boxes[&index].beads;
+
+        let total: u32 = beads.iter().sum();
+
+        // an empty matchbox means every move has lost before;
+        // pick any of them
+        let cell = if total == 0 {
+            let free: Vec<_> = (0..9)
+                .filter(|c| canonical_cell_free(game, symmetry, *c))
+                .collect();
+
+            free[self.rng.gen_range(0, free.len())]
+        } else {
+            let mut bead = self.rng.gen_range(0, total);
+
+            (0..9)
+                .find(|&c| {
+                    if bead < beads[c] {
+                        true
+                    } else {
+                        bead -= beads[c];
+                        false
+                    }
+                })
+                .unwrap()
+        };
+
+        self.moves.push((index, cell));
+
+        Some(cell_coord(from_canonical(symmetry, cell)))
+    }
+
+    /// Rewards or punishes every move made since the last call,
+    /// depending on how the game ended for `player`.
+    pub fn learn(&mut self, outcome: Outcome, player: XorZ) {
+        for (index, cell) in self.moves.drain(..) {
+            let beads = &mut self.boxes.get_mut(&index).unwrap().beads[cell];
+
+            match outcome.winner() {
+                Some(winner) if winner == player => *beads += WIN_BEADS,
+                Some(_) => *beads = beads.saturating_sub(LOSS_BEADS),
+                None => *beads += DRAW_BEADS,
+            }
+        }
+    }
+
+    /// Bead count of every legal move in the game's position.
+    pub fn weights(&self, game: &Game) -> Vec<(CellCoord, u32)> {
+        let (key, symmetry) = canonical_key(game);
+
+        let matchbox = self
+            .boxes
+            .get(&key)
+            .cloned()
+            .unwrap_or_else(|| new_matchbox(key));
+
+        game.legal_moves()
+            .map(|(x, y)| {
+                let cell = to_canonical(symmetry, cell_index(x, y));
+
+                ((x, y), matchbox.beads[cell])
+            })
+            .collect()
+    }
+
+    /// Number of positions MENACE has played from.
+    pub fn matchboxes(&self) -> usize {
+        self.boxes.len()
+    }
+
+    #[cfg(feature = "std")]
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let mut file = fs::File::create(path)?;
+
+        self.write_to(&mut file)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::read_from(BufReader::new(fs::File::open(path)?))
+    }
+
+    /// Writes one line per matchbox: its key followed by the bead
+    /// counts.
+    #[cfg(feature = "std")]
+    pub fn write_to<W: Write>(&self, out: &mut W) -> Result<(), Error> {
+        for (index, matchbox) in &self.boxes {
+            let beads: Vec<_> = matchbox.beads.iter().map(u32::to_string).collect();
+
+            writeln!(out, "{} {}", index, beads.join(" "))?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    pub fn read_from<R: BufRead>(input: R) -> Result<Self, Error> {
+        let mut menace = Self::new();
+
+        for (i, line) in input.lines().enumerate() {
+            let line = line?;
+            let invalid = || MemoryError::InvalidMatchbox { line: i + 1 };
+
+            let numbers: Vec<_> = line
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<_, _>>()
+                .map_err(|_| invalid())?;
+
+            if numbers.len() != 10 || numbers[0] as usize >= KEYS {
+                return Err(invalid().into());
+            }
+
+            let key = numbers[0] as usize;
+            let (x, z, blocked) = decode_key(key);
+
+            if canonical(x, z, blocked).0 != key {
+                return Err(invalid().into());
+            }
+
+            let mut beads = [0; 9];
+            beads.copy_from_slice(&numbers[1..]);
+
+            let taken = beads
+                .iter()
+                .enumerate()
+                .any(|(cell, &count)| count > 0 && (x | z | blocked) & 1 << cell != 0);
+
+            if taken {
+                return Err(invalid().into());
+            }
+
+            menace.boxes.insert(key, Matchbox { beads });
+        }
+
+        Ok(menace)
+    }
+
+    /// Index of the game's matchbox, created if needed, and the
+    /// symmetry that turns the game's position into the canonical one.
+    fn open_box(&mut self, game: &Game) -> (usize, usize) {
+        let (key, symmetry) = canonical_key(game);
+
+        self.boxes.entry(key).or_insert_with(|| new_matchbox(key));
+
+        (key, symmetry)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for Menace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Number of possible matchbox keys.
+#[cfg(feature = "std")]
+const KEYS: usize = symmetry::POSITIONS << 9;
+
+/// Matchbox key of a position: the position index plus
+/// `symmetry::POSITIONS` times the mask of blocked cells.
+fn box_key(x: u16, z: u16, blocked: u16) -> usize {
+    symmetry::index(x, z) + symmetry::POSITIONS * blocked as usize
+}
+
+/// Key of the game's matchbox, the lowest of all symmetric
+/// positions, and the symmetry that turns the game's position into
+/// the one of the key.
+fn canonical_key(game: &Game) -> (usize, usize) {
+    let (x, z) = (game.board.marks(XorZ::X), game.board.marks(XorZ::Z));
+
+    canonical(x, z, game.blocked)
+}
+
+/// Lowest key of all symmetric positions, and the symmetry that
+/// gives it.
+fn canonical(x: u16, z: u16, blocked: u16) -> (usize, usize) {
+    (0..symmetry::COUNT)
+        .map(|s| {
+            let apply = |mask| symmetry::apply(s, mask);
+
+            (box_key(apply(x), apply(z), apply(blocked)), s)
+        })
+        .min_by_key(|&(key, _)| key)
+        .unwrap()
+}
+
+/// A full matchbox for the position with the given key.
+fn new_matchbox(key: usize) -> Matchbox {
+    let (x, z, blocked) = decode_key(key);
+
+    Matchbox::new(x, z, blocked)
+}
+
+/// X marks, 0 marks and blocked cells of the position with the
+/// given key.
+fn decode_key(key: usize) -> (u16, u16, u16) {
+    let (mut x, mut z) = (0, 0);
+    let mut index = key % symmetry::POSITIONS;
+
+    for cell in 0..9 {
+        match index % 3 {
+            1 => x |= 1 << cell,
+            2 => z |= 1 << cell,
+            _ => {}
+        }
+
+        index /= 3;
+    }
+
+    (x, z, (key / symmetry::POSITIONS) as u16)
+}
+
+fn canonical_cell_free(game: &Game, symmetry: usize, cell: usize) -> bool {
+    let (x, y) = cell_coord(from_canonical(symmetry, cell));
+
+    !game.is_marked(x, y) && !game.is_blocked(x, y)
+}
+
+fn to_canonical(symmetry: usize, cell: usize) -> usize {
+    symmetry::apply(symmetry, 1 << cell).trailing_zeros() as usize
+}
+
+fn from_canonical(symmetry: usize, cell: usize) -> usize {
+    to_canonical(symmetry::inverse(symmetry), cell)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn learns_from_wins() {
+        let mut menace = Menace::with_seed(7);
+        let mut g = Game::new();
+
+        let (x, y) = menace.choose_move(&g).unwrap();
+        let before = weight(&menace, &g, (x, y));
+
+        g.mark(x, y).unwrap();
+
+        menace.learn(
+            Outcome::Win(XorZ::X, WinReason::Lines(WinLine::DLeft.into())),
+            XorZ::X,
+        );
+
+        assert_eq!(weight(&menace, &Game::new(), (x, y)), before + WIN_BEADS);
+    }
+
+    #[test]
+    fn symmetric_positions_share_a_matchbox() {
+        let mut menace = Menace::with_seed(1);
+
+        for &(x, y) in &[(0, 0), (2, 0), (0, 2), (2, 2)] {
+            let mut g = Game::new();
+            g.mark(x, y).unwrap();
+
+            menace.choose_move(&g).unwrap();
+        }
+
+        assert_eq!(menace.matchboxes(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn save_and_load() {
+        let mut menace = Menace::with_seed(3);
+        let mut g = Game::new();
+
+        while let Some((x, y)) = menace.choose_move(&g) {
+            g.mark(x, y).unwrap();
+        }
+
+        menace.learn(g.get_outcome().unwrap(), XorZ::X);
+
+        let mut saved = Vec::new();
+        menace.write_to(&mut saved).unwrap();
+
+        let loaded = Menace::read_from(&saved[..]).unwrap();
+
+        assert_eq!(loaded.boxes, menace.boxes);
+
+        assert!(Menace::read_from(&b"12 1 2 3\n"[..]).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn load_rejects_impossible_matchboxes() {
+        let invalid = |line| match Menace::read_from(line) {
+            Err(Error::Memory(MemoryError::InvalidMatchbox { line })) => line,
+            other => panic!("{:?}", other.map(|m| m.matchboxes())),
+        };
+
+        // X in the top left corner; beads on its cell
+        assert_eq!(invalid(&b"1 1 1 1 1 1 1 1 1 1\n"[..]), 1);
+        // X in the top right corner is the same position turned
+        assert_eq!(
+            invalid(&b"1 0 1 1 1 1 1 1 1 1\n9 1 1 0 1 1 1 1 1 1\n"[..]),
+            2
+        );
+        // a blocked center
+        let blocked = symmetry::POSITIONS * (1 << 4);
+        let line = format!("{} 1 1 1 1 1 1 1 1 1\n", blocked);
+        assert_eq!(invalid(line.as_bytes()), 1);
+
+        let line = format!("{} 1 1 1 1 0 1 1 1 1\n", blocked);
+        assert!(Menace::read_from(line.as_bytes()).is_ok());
+    }
+
+    fn weight(menace: &Menace, game: &Game, coord: CellCoord) -> u32 {
+        menace
+            .weights(game)
+            .into_iter()
+            .find(|(c, _)| *c == coord)
+            .unwrap()
+            .1
+    }
+}
@@ -0,0 +1,372 @@
+//! Interchangeable players and a driver that plays a game between
+//! any two of them.
+use alloc::collections::VecDeque;
+
+use rand::{rngs::StdRng, seq::IteratorRandom, SeedableRng};
+
+use super::menace::Menace;
+#[cfg(feature = "std")]
+use super::train::QPolicy;
+use super::*;
+
+/// The cell a player wants to mark, or `None` if it has not decided
+/// yet, e.g. while waiting for a click or a message from a peer.
+pub type MoveChoice = Result<Option<CellCoord>, Error>;
+
+pub trait Player {
+    /// Asked repeatedly, while it is the player's turn, until a move
+    /// is returned.
+    fn choose_move(&mut self, game: &Game) -> MoveChoice;
+
+    /// Called once when the game ends; `side` is the side the
+    /// player played.
+    fn game_ended(&mut self, _game: &Game, _side: XorZ) {}
+}
+
+/// Plays moves given to it from the outside, in order: mouse
+/// clicks, moves received over the network or a scripted game.
+#[derive(Debug, Clone, Default)]
+pub struct QueuedPlayer {
+    moves: VecDeque<CellCoord>,
+}
+
+impl QueuedPlayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_moves<I: IntoIterator<Item = CellCoord>>(moves: I) -> Self {
+        Self {
+            moves: moves.into_iter().collect(),
+        }
+    }
+
+    pub fn push(&mut self, coord: CellCoord) {
+        self.moves.push_back(coord);
+    }
+
+    pub fn clear(&mut self) {
+        self.moves.clear();
+    }
+}
+
+impl Player for QueuedPlayer {
+    fn choose_move(&mut self, _game: &Game) -> MoveChoice {
+        Ok(self.moves.pop_front())
+    }
+}
+
+/// Picks any legal move.
+pub struct RandomPlayer {
+    rng: StdRng,
+}
+
+impl RandomPlayer {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Player for RandomPlayer {
+    fn choose_move(&mut self, game: &Game) -> MoveChoice {
+        Ok(game.legal_moves().choose(&mut self.rng))
+    }
+}
+
+/// Never makes a mistake; picks at random between equally good moves.
+pub struct PerfectPlayer {
+    rng: StdRng,
+}
+
+impl PerfectPlayer {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Player for PerfectPlayer {
+    fn choose_move(&mut self, game: &Game) -> MoveChoice {
+        // the tablebase takes X to move when both players have as
+        // many marks, and knows nothing about blocked cells
+        let (x, z) = (game.board.marks(XorZ::X), game.board.marks(XorZ::Z));
+        let table_turn = if x.count() == z.count() {
+            XorZ::X
+        } else {
+            XorZ::Z
+        };
+
+        let best = match tablebase::lookup_board(&game.board) {
+            Some(entry) if table_turn == game.turn() && game.blocked == 0 => {
+                entry.best_moves().collect()
+            }
+            _ => solver::best_moves(game),
+        };
+
+        Ok(best.into_iter().choose(&mut self.rng))
+    }
+}
+
+impl Player for Menace {
+    fn choose_move(&mut self, game: &Game) -> MoveChoice {
+        Ok(Menace::choose_move(self, game))
+    }
+
+    fn game_ended(&mut self, game: &Game, side: XorZ) {
+        if let Some(outcome) = game.get_outcome() {
+            self.learn(outcome, side);
+        }
+    }
+}
+
+impl<P: Player + ?Sized> Player for Box<P> {
+    fn choose_move(&mut self, game: &Game) -> MoveChoice {
+        (**self).choose_move(game)
+    }
+
+    fn game_ended(&mut self, game: &Game, side: XorZ) {
+        (**self).game_ended(game, side)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Player for QPolicy {
+    fn choose_move(&mut self, game: &Game) -> MoveChoice {
+        Ok(QPolicy::choose_move(self, game))
+    }
+}
+
+/// Asks the player whose turn it is for moves and makes them.
+pub struct GameDriver<X: Player, Z: Player> {
+    game: Game,
+    x: X,
+    z: Z,
+    /// have the players been told that the game ended?
+    notified: bool,
+}
+
+impl<X: Player, Z: Player> GameDriver<X, Z> {
+    pub fn new(game: Game, x: X, z: Z) -> Self {
+        Self {
+            game,
+            x,
+            z,
+            notified: false,
+        }
+    }
+
+    /// Makes the next move, if the player whose turn it is has
+    /// decided on one.
+    pub fn step(&mut self) -> Result<Option<Outcome>, Error> {
+        if self.game.ended() {
+            return Err(MarkError::GameEnded.into());
+        }
+
+        if let Some(outcome) = self.game.check_time() {
+            self.notify_ended();
+
+            return Ok(Some(outcome));
+        }
+
+        let choice = match self.game.turn() {
+            XorZ::X => self.x.choose_move(&self.game)?,
+            XorZ::Z => self.z.choose_move(&self.game)?,
+        };
+
+        let outcome = match choice {
+            Some((x, y)) => self.game.mark(x, y)?,
+            None => None,
+        };
+
+        if outcome.is_some() {
+            self.notify_ended();
+        }
+
+        Ok(outcome)
+    }
+
+    /// Plays until the game ends; fails if a player has no move ready.
+    pub fn run(&mut self) -> Result<Outcome, Error> {
+        loop {
+            let turn = self.game.turn();
+            let marked = self.game.empty_cells().count();
+
+            if let Some(outcome) = self.step()? {
+                return Ok(outcome);
+            }
+
+            if self.game.empty_cells().count() == marked {
+                return Err(PlayerError::NoMoveReady { player: turn }.into());
+            }
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn x_player_mut(&mut self) -> &mut X {
+        &mut self.x
+    }
+
+    pub fn z_player_mut(&mut self) -> &mut Z {
+        &mut self.z
+    }
+
+    pub fn into_players(self) -> (X, Z) {
+        (self.x, self.z)
+    }
+
+    fn notify_ended(&mut self) {
+        if !self.notified {
+            self.x.game_ended(&self.game, XorZ::X);
+            self.z.game_ended(&self.game, XorZ::Z);
+            self.notified = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bitboard::Geometry;
+    use crate::engine::Engine;
+
+    #[test]
+    fn scripted_game() {
+        let x = QueuedPlayer::with_moves(vec![(0, 0), (0, 1), (0, 2)]);
+        let z = QueuedPlayer::with_moves(vec![(1, 0), (1, 1)]);
+
+        let outcome = GameDriver::new(Game::new(), x, z).run().unwrap();
+
+        assert_eq!(
+            outcome,
+            Outcome::Win(XorZ::X, WinReason::Lines(WinLine::VLeft.into()))
+        );
+    }
+
+    #[test]
+    fn waiting_for_a_move() {
+        let mut driver = GameDriver::new(Game::new(), QueuedPlayer::new(), QueuedPlayer::new());
+
+        assert!(driver.step().unwrap().is_none());
+        assert_eq!(driver.game().turn(), XorZ::X);
+
+        driver.x_player_mut().push((1, 1));
+        driver.step().unwrap();
+
+        assert_eq!(driver.game().turn(), XorZ::Z);
+        assert!(driver.run().is_err());
+    }
+
+    #[test]
+    fn perfect_players_draw() {
+        for seed in 0..10 {
+            let mut driver = GameDriver::new(
+                Game::new(),
+                PerfectPlayer::new(seed),
+                PerfectPlayer::new(seed + 1),
+            );
+
+            assert!(matches!(driver.run().unwrap(), Outcome::Draw(_)));
+        }
+    }
+
+    #[test]
+    fn perfect_player_never_loses() {
+        for seed in 0..20 {
+            let mut driver = GameDriver::new(
+                Game::new(),
+                RandomPlayer::new(seed),
+                PerfectPlayer::new(seed),
+            );
+
+            if let Outcome::Win(XorZ::X, _) = driver.run().unwrap() {
+                panic!("random player won with seed {}", seed);
+            }
+        }
+    }
+
+    #[test]
+    fn perfect_player_with_handicap() {
+        let game = GameBuilder::new()
+            .with_handicap_mark(0, 0, XorZ::X)
+            .with_handicap_mark(1, 0, XorZ::X)
+            .with_handicap_mark(2, 0, XorZ::Z)
+            .build()
+            .unwrap();
+
+        // X has a mark more, yet moves first
+        let best = solver::best_moves(&game);
+        assert_eq!(best, vec![(0, 1), (1, 1), (0, 2), (1, 2), (2, 2)]);
+
+        for seed in 0..20 {
+            let chosen = PerfectPlayer::new(seed)
+                .choose_move(&game)
+                .unwrap()
+                .unwrap();
+
+            assert!(best.contains(&chosen), "{:?}", chosen);
+        }
+    }
+
+    #[test]
+    fn menace_learns_through_the_driver() {
+        let mut menace = Menace::with_seed(5);
+
+        for seed in 0..50 {
+            let mut driver = GameDriver::new(Game::new(), menace, PerfectPlayer::new(seed));
+            driver.run().unwrap();
+
+            menace = driver.into_players().0;
+        }
+
+        assert!(menace.matchboxes() > 0);
+    }
+
+    #[test]
+    fn players_avoid_blocked_cells() {
+        let players = |seed| {
+            let players: Vec<Box<dyn Player>> = vec![
+                Box::new(RandomPlayer::new(seed)),
+                Box::new(PerfectPlayer::new(seed)),
+                Box::new(Menace::with_seed(seed)),
+                Box::new(Engine::<u16>::new(Geometry::CLASSIC, 9).unwrap()),
+            ];
+
+            #[cfg(feature = "std")]
+            let players = {
+                let mut players = players;
+                players.push(Box::new(QPolicy::default()));
+                players
+            };
+
+            players
+        };
+
+        for seed in 0..50 {
+            let game = GameBuilder::new()
+                .with_random_blocked_cells(3, seed)
+                .build()
+                .unwrap();
+
+            for (x, z) in players(seed).into_iter().zip(players(seed + 1)) {
+                let mut driver = GameDriver::new(game.clone(), x, RandomPlayer::new(seed));
+                driver.run().unwrap();
+
+                let mut driver = GameDriver::new(game.clone(), RandomPlayer::new(seed), z);
+                driver.run().unwrap();
+            }
+        }
+
+        // queued moves are played as they are
+        let game = GameBuilder::new().with_blocked_cell(1, 1).build().unwrap();
+        let x = QueuedPlayer::with_moves(vec![(1, 1)]);
+        let mut driver = GameDriver::new(game, x, QueuedPlayer::new());
+
+        assert!(driver.step().is_err());
+    }
+}
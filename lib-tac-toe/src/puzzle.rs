@@ -0,0 +1,627 @@
+//! "To move and win in N" puzzles.
+//!
+//! `Puzzle`s are found among the positions of a `Game`, with any of
+//! its variants: who starts, handicap marks, blocked cells and early
+//! draws. `BoardPuzzle`s are found on a `Bitboard` of any `Geometry`,
+//! e.g. 4x4 boards with 3 in a row.
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+
+use super::bitboard::{cells, Bitboard, Geometry, Mask};
+use super::record::{self, GameRecord, RecordResult};
+use super::*;
+
+/// What the search needs to know about a position.
+trait Position: Clone {
+    fn to_move(&self) -> XorZ;
+
+    fn is_over(&self) -> bool;
+
+    fn winner(&self) -> Option<XorZ>;
+
+    /// Legal moves; none once the game is over.
+    fn moves(&self) -> Vec<CellCoord>;
+
+    /// The position after the player to move marks `cell`.
+    fn try_play(&self, cell: CellCoord) -> Result<Self, MarkError>;
+
+    fn play(&self, cell: CellCoord) -> Self {
+        self.try_play(cell).unwrap()
+    }
+}
+
+impl Position for Game {
+    fn to_move(&self) -> XorZ {
+        self.turn()
+    }
+
+    fn is_over(&self) -> bool {
+        self.ended()
+    }
+
+    fn winner(&self) -> Option<XorZ> {
+        self.get_outcome().and_then(Outcome::winner)
+    }
+
+    fn moves(&self) -> Vec<CellCoord> {
+        self.legal_moves().collect()
+    }
+
+    fn try_play(&self, (x, y): CellCoord) -> Result<Self, MarkError> {
+        let mut next = self.clone();
+        next.mark(x, y)?;
+
+        Ok(next)
+    }
+}
+
+/// The rules of a board of some `Geometry`.
+struct Rules<M: Mask> {
+    geometry: Geometry,
+    win_masks: Vec<M>,
+    full: M,
+}
+
+impl<M: Mask> Rules<M> {
+    fn new(geometry: Geometry) -> Self {
+        Self {
+            geometry,
+            win_masks: geometry.win_masks(),
+            full: geometry.full(),
+        }
+    }
+}
+
+/// A `Bitboard` position, X moving first.
+#[derive(Clone)]
+struct BoardPosition<'a, M: Mask> {
+    rules: &'a Rules<M>,
+    board: Bitboard<M>,
+}
+
+impl<M: Mask> Position for BoardPosition<'_, M> {
+    fn to_move(&self) -> XorZ {
+        if self.board.marks(XorZ::X).count() > self.board.marks(XorZ::Z).count() {
+            XorZ::Z
+        } else {
+            XorZ::X
+        }
+    }
+
+    fn is_over(&self) -> bool {
+        self.winner().is_some() || self.board.occupied() == self.rules.full
+    }
+
+    fn winner(&self) -> Option<XorZ> {
+        [XorZ::X, XorZ::Z]
+            .iter()
+            .copied()
+            .find(|player| self.board.wins(*player, &self.rules.win_masks))
+    }
+
+    fn moves(&self) -> Vec<CellCoord> {
+        if self.is_over() {
+            return Vec::new();
+        }
+
+        cells(self.rules.full & !self.board.occupied())
+            .map(|cell| self.rules.geometry.coord(cell))
+            .collect()
+    }
+
+    fn try_play(&self, (x, y): CellCoord) -> Result<Self, MarkError> {
+        let max = self.rules.geometry.size - 1;
+
+        if x > max || y > max {
+            return Err(MarkError::OutOfBounds {
+                index: x.max(y),
+                max,
+            });
+        }
+
+        if self.is_over() {
+            return Err(MarkError::GameEnded);
+        }
+
+        let cell = self.rules.geometry.index(x, y);
+
+        if self.board.cell(cell) != CellState::Empty {
+            return Err(MarkError::CellMarked);
+        }
+
+        let mut next = self.clone();
+        next.board.place(cell, self.to_move());
+
+        Ok(next)
+    }
+}
+
+/// A position where the player to move has exactly one move that
+/// forces a win within `moves` of its own moves, and no faster win.
+#[derive(Debug, Clone)]
+pub struct Puzzle {
+    game: Game,
+    moves: u8,
+    solution: CellCoord,
+    difficulty: u32,
+}
+
+/// How far a solution line gets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Attempt {
+    /// The line ends with a win in time.
+    Solved,
+    /// Every move so far keeps the forced win, but the game goes on.
+    Incomplete,
+    /// The move at this index of the line lets the win slip.
+    WrongMove(usize),
+}
+
+impl Puzzle {
+    /// Checks whether the position is a puzzle, without searching for
+    /// one.
+    pub fn from_game(game: &Game, moves: u8) -> Option<Self> {
+        let game = game.searchable();
+        let (solution, difficulty) = solve(&game, moves)?;
+
+        Some(Self {
+            game,
+            moves,
+            solution,
+            difficulty,
+        })
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Moves of the player to move needed to win.
+    pub fn moves(&self) -> u8 {
+        self.moves
+    }
+
+    /// The only move that wins in time.
+    pub fn solution(&self) -> CellCoord {
+        self.solution
+    }
+
+    /// Positions in which the solver has to find the right move,
+    /// times the moves to choose from at the start.
+    pub fn difficulty(&self) -> u32 {
+        self.difficulty
+    }
+
+    /// Position notation of the puzzle.
+    pub fn position(&self) -> String {
+        record::position_name(&self.game)
+    }
+
+    /// An unfinished record of the puzzle, with the solution left out.
+    pub fn record(&self) -> GameRecord {
+        let mut record = GameRecord::new();
+
+        record.set_tag(record::POSITION, &self.position());
+        record.set_tag("WinIn", &self.moves.to_string());
+        record.set_tag("Difficulty", &self.difficulty.to_string());
+        record.result = RecordResult::Unfinished;
+
+        record
+    }
+
+    /// Plays a solution line of moves by both players, checking that
+    /// every move of the player to move keeps a win in time; the
+    /// opponent may answer with any move.
+    pub fn check(&self, line: &[CellCoord]) -> Result<Attempt, MarkError> {
+        check_line(&self.game, self.moves, line)
+    }
+}
+
+/// A puzzle on a `Bitboard` of any `Geometry`, where X moved first.
+#[derive(Debug, Clone)]
+pub struct BoardPuzzle<M: Mask> {
+    geometry: Geometry,
+    board: Bitboard<M>,
+    moves: u8,
+    solution: CellCoord,
+    difficulty: u32,
+}
+
+impl<M: Mask> BoardPuzzle<M> {
+    /// Checks whether the position is a puzzle, without searching for
+    /// one.
+    pub fn from_board(geometry: Geometry, board: &Bitboard<M>, moves: u8) -> Option<Self> {
+        let rules = Rules::new(geometry);
+        let position = BoardPosition {
+            rules: &rules,
+            board: *board,
+        };
+        let (solution, difficulty) = solve(&position, moves)?;
+
+        Some(Self {
+            geometry,
+            board: *board,
+            moves,
+            solution,
+            difficulty,
+        })
+    }
+
+    pub fn geometry(&self) -> Geometry {
+        self.geometry
+    }
+
+    pub fn board(&self) -> &Bitboard<M> {
+        &self.board
+    }
+
+    /// Moves of the player to move needed to win.
+    pub fn moves(&self) -> u8 {
+        self.moves
+    }
+
+    /// The only move that wins in time.
+    pub fn solution(&self) -> CellCoord {
+        self.solution
+    }
+
+    /// Same measure as `Puzzle::difficulty`.
+    pub fn difficulty(&self) -> u32 {
+        self.difficulty
+    }
+
+    /// Position notation of the puzzle, with as many rows and cells
+    /// per row as the board has.
+    pub fn position(&self) -> String {
+        let rules = Rules::new(self.geometry);
+        let position = BoardPosition {
+            rules: &rules,
+            board: self.board,
+        };
+        let size = self.geometry.size;
+
+        let rows: Vec<String> = (0..size)
+            .map(|y| {
+                (0..size)
+                    .map(|x| match self.board.cell(self.geometry.index(x, y)) {
+                        CellState::Marked(XorZ::X) => 'X',
+                        CellState::Marked(XorZ::Z) => '0',
+                        _ => '.',
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let to_move = match position.to_move() {
+            XorZ::X => 'X',
+            XorZ::Z => '0',
+        };
+
+        format!("{} {}", rows.join("/"), to_move)
+    }
+
+    /// Same as `Puzzle::check`.
+    pub fn check(&self, line: &[CellCoord]) -> Result<Attempt, MarkError> {
+        let rules = Rules::new(self.geometry);
+        let position = BoardPosition {
+            rules: &rules,
+            board: self.board,
+        };
+
+        check_line(&position, self.moves, line)
+    }
+}
+
+/// Finds all puzzles with a win in `moves` among the positions
+/// reachable from `start`, one per group of symmetric positions,
+/// with the player to move being `attacker`.
+pub fn generate(start: &Game, moves: u8, attacker: XorZ) -> Vec<Puzzle> {
+    let mut seen = BTreeSet::new();
+    let mut puzzles = Vec::new();
+    let mut stack = vec![start.searchable()];
+
+    while let Some(game) = stack.pop() {
+        if !seen.insert((canonical_marks(&game), game.turn())) {
+            continue;
+        }
+
+        if game.turn() == attacker {
+            if let Some(puzzle) = Puzzle::from_game(&game, moves) {
+                puzzles.push(puzzle);
+            }
+        }
+
+        stack.extend(game.moves().into_iter().map(|cell| game.play(cell)));
+    }
+
+    puzzles.sort_by_key(|p| p.difficulty);
+
+    puzzles
+}
+
+/// Finds all puzzles with a win in `moves` among the positions of
+/// the board with at most `max_marks` marks, one per group of
+/// symmetric positions, with the player to move being `attacker`.
+pub fn generate_on<M: Mask>(
+    geometry: Geometry,
+    moves: u8,
+    attacker: XorZ,
+    max_marks: u32,
+) -> Vec<BoardPuzzle<M>> {
+    let rules = Rules::<M>::new(geometry);
+    let mut seen = BTreeSet::new();
+    let mut puzzles = Vec::new();
+    let mut stack = vec![BoardPosition {
+        rules: &rules,
+        board: Bitboard::new(),
+    }];
+
+    while let Some(position) = stack.pop() {
+        let (canonical, _) = geometry.canonical(&position.board);
+
+        if !seen.insert(canonical) {
+            continue;
+        }
+
+        if position.to_move() == attacker {
+            if let Some(puzzle) = BoardPuzzle::from_board(geometry, &position.board, moves) {
+                puzzles.push(puzzle);
+            }
+        }
+
+        if Mask::count(position.board.occupied()) < max_marks {
+            stack.extend(position.moves().into_iter().map(|cell| position.play(cell)));
+        }
+    }
+
+    puzzles.sort_by_key(|p| p.difficulty);
+
+    puzzles
+}
+
+/// The lowest X, 0 and blocked cell masks of all symmetric
+/// positions, each moved by the same symmetry.
+fn canonical_marks(game: &Game) -> (u16, u16, u16) {
+    let (x, z) = (game.board.marks(XorZ::X), game.board.marks(XorZ::Z));
+
+    (0..symmetry::COUNT)
+        .map(|s| {
+            let apply = |mask| symmetry::apply(s, mask);
+
+            (apply(x), apply(z), apply(game.blocked))
+        })
+        .min()
+        .unwrap()
+}
+
+/// The only move that wins within `moves`, if there is one and no
+/// faster win, and the difficulty of finding the win.
+fn solve<P: Position>(position: &P, moves: u8) -> Option<(CellCoord, u32)> {
+    if position.is_over() || moves == 0 || wins_within(position, moves - 1) {
+        return None;
+    }
+
+    let legal = position.moves();
+    let winning: Vec<_> = legal
+        .iter()
+        .filter(|&&cell| attacker_wins_after(&position.play(cell), position.to_move(), moves))
+        .collect();
+
+    if winning.len() != 1 {
+        return None;
+    }
+
+    let difficulty = proof_size(position, moves) * legal.len() as u32;
+
+    Some((*winning[0], difficulty))
+}
+
+/// Plays a solution line, see `Puzzle::check`.
+fn check_line<P: Position>(start: &P, moves: u8, line: &[CellCoord]) -> Result<Attempt, MarkError> {
+    let attacker = start.to_move();
+    let mut left = moves;
+    let mut position = start.clone();
+
+    for (i, &cell) in line.iter().enumerate() {
+        let mover = position.to_move();
+
+        position = position.try_play(cell)?;
+
+        if mover == attacker {
+            if !attacker_wins_after(&position, attacker, left) {
+                return Ok(Attempt::WrongMove(i));
+            }
+
+            left -= 1;
+        }
+
+        if position.is_over() {
+            // only the attacker can end the game while keeping the win
+            return Ok(Attempt::Solved);
+        }
+    }
+
+    Ok(Attempt::Incomplete)
+}
+
+/// Can the player to move win with at most `moves` of its own moves?
+fn wins_within<P: Position>(position: &P, moves: u8) -> bool {
+    moves > 0
+        && position
+            .moves()
+            .into_iter()
+            .any(|cell| attacker_wins_after(&position.play(cell), position.to_move(), moves))
+}
+
+/// After a move by `attacker` that used one of its `moves`, does it
+/// still win in time against every reply?
+fn attacker_wins_after<P: Position>(position: &P, attacker: XorZ, moves: u8) -> bool {
+    if position.is_over() {
+        return position.winner() == Some(attacker);
+    }
+
+    position.moves().into_iter().all(|cell| {
+        let next = position.play(cell);
+
+        !next.is_over() && wins_within(&next, moves - 1)
+    })
+}
+
+/// Number of positions in which the attacker must pick a winning
+/// move, following the first one it finds.
+fn proof_size<P: Position>(position: &P, moves: u8) -> u32 {
+    let attacker = position.to_move();
+
+    let winning = position
+        .moves()
+        .into_iter()
+        .map(|cell| position.play(cell))
+        .find(|next| attacker_wins_after(next, attacker, moves));
+
+    let next = match winning {
+        Some(next) => next,
+        None => return 0,
+    };
+
+    let replies: u32 = next
+        .moves()
+        .into_iter()
+        .map(|cell| proof_size(&next.play(cell), moves - 1))
+        .sum();
+
+    1 + replies
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn win_in_one() {
+        let game = record::read_position("XX./00./... X").unwrap();
+        let puzzle = Puzzle::from_game(&game, 1).unwrap();
+
+        assert_eq!(puzzle.solution(), (2, 0));
+        assert_eq!(puzzle.check(&[(2, 0)]).unwrap(), Attempt::Solved);
+        assert_eq!(puzzle.check(&[(2, 2)]).unwrap(), Attempt::WrongMove(0));
+        assert!(puzzle.check(&[(0, 0)]).is_err());
+    }
+
+    #[test]
+    fn blocked_cells() {
+        let start = GameBuilder::new().with_blocked_cell(0, 0).build().unwrap();
+        let puzzles = generate(&start, 2, XorZ::X);
+
+        // every reachable puzzle position, then one per symmetry class
+        let mut positions = BTreeSet::new();
+        let mut seen = BTreeSet::new();
+        let mut stack = vec![start.searchable()];
+
+        while let Some(game) = stack.pop() {
+            let (x, z) = (game.board.marks(XorZ::X), game.board.marks(XorZ::Z));
+
+            if !seen.insert((x, z)) {
+                continue;
+            }
+
+            if game.turn() == XorZ::X && Puzzle::from_game(&game, 2).is_some() {
+                positions.insert((x, z, game.blocked));
+            }
+
+            stack.extend(game.moves().into_iter().map(|cell| game.play(cell)));
+        }
+
+        let mut classes = 0;
+
+        while let Some(&(x, z, blocked)) = positions.iter().next() {
+            for s in 0..symmetry::COUNT {
+                let apply = |mask| symmetry::apply(s, mask);
+                positions.remove(&(apply(x), apply(z), apply(blocked)));
+            }
+
+            classes += 1;
+        }
+
+        assert!(classes > 0);
+        assert_eq!(puzzles.len(), classes);
+    }
+
+    #[test]
+    fn win_in_two() {
+        let puzzles = generate(&Game::new(), 2, XorZ::X);
+
+        assert!(!puzzles.is_empty());
+
+        for puzzle in &puzzles {
+            assert_eq!(puzzle.moves(), 2);
+            assert!(Puzzle::from_game(puzzle.game(), 1).is_none());
+
+            let record = GameRecord::read(&puzzle.record().to_string()).unwrap();
+            assert_eq!(record.replay().unwrap().turn(), XorZ::X);
+        }
+
+        let hardest = puzzles.last().unwrap();
+        let mut game = hardest.game().clone();
+        let first = hardest.solution();
+
+        game.mark(first.0, first.1).unwrap();
+
+        // the opponent blocks one threat, the other one wins
+        let reply = game.legal_moves().next().unwrap();
+        game.mark(reply.0, reply.1).unwrap();
+
+        let finish = game
+            .legal_moves()
+            .find(|&(x, y)| {
+                let mut g = game.clone();
+                g.mark(x, y).unwrap().is_some()
+            })
+            .unwrap();
+
+        assert_eq!(
+            hardest.check(&[first, reply, finish]).unwrap(),
+            Attempt::Solved
+        );
+        assert_eq!(hardest.check(&[first]).unwrap(), Attempt::Incomplete);
+    }
+
+    #[test]
+    fn board_win_in_one() {
+        let geometry = Geometry::new(4, 3);
+        let mut board = Bitboard::<u16>::new();
+
+        for &((x, y), player) in &[
+            ((0, 0), XorZ::X),
+            ((3, 0), XorZ::Z),
+            ((1, 1), XorZ::X),
+            ((0, 3), XorZ::Z),
+        ] {
+            board.place(geometry.index(x, y), player);
+        }
+
+        let puzzle = BoardPuzzle::from_board(geometry, &board, 1).unwrap();
+
+        assert_eq!(puzzle.position(), "X..0/.X../..../0... X");
+        assert_eq!(puzzle.solution(), (2, 2));
+        assert_eq!(puzzle.check(&[(2, 2)]).unwrap(), Attempt::Solved);
+        assert_eq!(puzzle.check(&[(2, 1)]).unwrap(), Attempt::WrongMove(0));
+        assert!(puzzle.check(&[(0, 0)]).is_err());
+        assert!(puzzle.check(&[(4, 0)]).is_err());
+    }
+
+    #[test]
+    fn board_win_in_two() {
+        let geometry = Geometry::new(4, 3);
+        let puzzles = generate_on::<u16>(geometry, 2, XorZ::X, 4);
+
+        assert!(!puzzles.is_empty());
+
+        for puzzle in &puzzles {
+            assert_eq!(puzzle.moves(), 2);
+            assert!(BoardPuzzle::from_board(geometry, puzzle.board(), 1).is_none());
+            assert!(puzzle.board().occupied().count() <= 4);
+        }
+    }
+}
@@ -0,0 +1,757 @@
+//! Text records of games, modelled on chess PGN:
+//!
+//! ```text
+//! [PlayerX "Alice"]
+//! [Player0 "Bob"]
+//! [Date "2019.05.01"]
+//! [Result "1-0"]
+//!
+//! 1. b2 a1 2. c1 {threatens a3} b1? 3. a3! 1-0
+//! ```
+//!
+//! Cells are named by their column, `a` to `c`, and their row, `1` to
+//! `3`, both counted from the top left corner. A `}` or `\` in a
+//! comment is written with a `\` before it, like a `"` or `\` in a
+//! tag value.
+//!
+//! Positions are written row by row from the top, separated by `/`,
+//! followed by the player to move, e.g. `X0./.X./... 0`. Blocked
+//! cells are written as `#`.
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::fmt;
+use core::iter::Peekable;
+use core::str::Chars;
+#[cfg(feature = "std")]
+use std::io::Write;
+
+use super::*;
+
+pub const PLAYER_X: &str = "PlayerX";
+pub const PLAYER_Z: &str = "Player0";
+pub const DATE: &str = "Date";
+/// only `classic` is supported
+pub const VARIANT: &str = "Variant";
+/// only `3` is supported
+pub const BOARD_SIZE: &str = "BoardSize";
+pub const TIME_CONTROL: &str = "TimeControl";
+/// `X` or `0`; X starts if missing
+pub const START: &str = "Start";
+/// position before the first move, overrides `Start`
+pub const POSITION: &str = "Position";
+const RESULT: &str = "Result";
+
+/// Moves are wrapped to lines no longer than this, where possible.
+const LINE_WIDTH: usize = 79;
+
+/// Name of a cell, e.g. `b2` for the center.
+pub fn cell_name((x, y): CellCoord) -> String {
+    format!("{}{}", (b'a' + x as u8) as char, y + 1)
+}
+
+pub fn parse_cell(name: &str) -> Option<CellCoord> {
+    let mut chars = name.chars();
+
+    let x = match chars.next()? {
+        c @ 'a'..='c' => c as usize - 'a' as usize,
+        _ => return None,
+    };
+    let y = match chars.next()? {
+        c @ '1'..='3' => c as usize - '1' as usize,
+        _ => return None,
+    };
+
+    if chars.next().is_some() {
+        return None;
+    }
+
+    Some((x, y))
+}
+
+pub fn position_name(game: &Game) -> String {
+    let state = game.board_state();
+    let rows: Vec<String> = (0..ROW_SIZE)
+        .map(|y| {
+            (0..ROW_SIZE)
+                .map(|x| match state[x][y] {
+                    CellState::Empty => '.',
+                    CellState::Marked(xz) => player_char(xz),
+                    CellState::Blocked => '#',
+                })
+                .collect()
+        })
+        .collect();
+
+    format!("{} {}", rows.join("/"), player_char(game.turn()))
+}
+
+/// A game starting from the position, with the marks already on the
+/// board counted as handicap marks.
+pub fn read_position(text: &str) -> Result<Game, SetupError> {
+    let mut parts = text.split(' ');
+    let (board, to_move) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(board), Some(to_move), None) => (board, to_move),
+        _ => return Err(SetupError::InvalidPosition),
+    };
+
+    let starting = match to_move {
+        "X" => XorZ::X,
+        "0" => XorZ::Z,
+        _ => return Err(SetupError::InvalidPosition),
+    };
+
+    let rows: Vec<_> = board.split('/').collect();
+    if rows.len() != ROW_SIZE {
+        return Err(SetupError::InvalidPosition);
+    }
+
+    let mut builder = GameBuilder::new().with_starting_player(starting);
+
+    for (y, row) in rows.iter().enumerate() {
+        if row.chars().count() != ROW_SIZE {
+            return Err(SetupError::InvalidPosition);
+        }
+
+        for (x, c) in row.chars().enumerate() {
+            builder = match c {
+                'X' => builder.with_handicap_mark(x, y, XorZ::X),
+                '0' => builder.with_handicap_mark(x, y, XorZ::Z),
+                '#' => builder.with_blocked_cell(x, y),
+                '.' => builder,
+                _ => return Err(SetupError::InvalidPosition),
+            };
+        }
+    }
+
+    builder.build()
+}
+
+fn player_char(player: XorZ) -> char {
+    match player {
+        XorZ::X => 'X',
+        XorZ::Z => '0',
+    }
+}
+
+/// How a move is judged, written right after it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Annotation {
+    /// `!!`
+    Brilliant,
+    /// `!`
+    Good,
+    /// `!?`
+    Interesting,
+    /// `?!`
+    Dubious,
+    /// `?`
+    Mistake,
+    /// `??`
+    Blunder,
+}
+
+impl Annotation {
+    const ALL: [Annotation; 6] = [
+        Annotation::Brilliant,
+        Annotation::Good,
+        Annotation::Interesting,
+        Annotation::Dubious,
+        Annotation::Mistake,
+        Annotation::Blunder,
+    ];
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Annotation::Brilliant => "!!",
+            Annotation::Good => "!",
+            Annotation::Interesting => "!?",
+            Annotation::Dubious => "?!",
+            Annotation::Mistake => "?",
+            Annotation::Blunder => "??",
+        }
+    }
+
+    pub fn from_symbol(symbol: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|a| a.symbol() == symbol)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedMove {
+    pub cell: CellCoord,
+    pub annotation: Option<Annotation>,
+    pub comment: Option<String>,
+}
+
+impl RecordedMove {
+    pub fn new(cell: CellCoord) -> Self {
+        Self {
+            cell,
+            annotation: None,
+            comment: None,
+        }
+    }
+}
+
+/// The result that ends the move list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RecordResult {
+    /// `1-0` for X, `0-1` for 0
+    Win(XorZ),
+    /// `1/2-1/2`
+    Draw,
+    /// `*`, for a game that is still being played
+    #[default]
+    Unfinished,
+}
+
+impl RecordResult {
+    pub fn of(outcome: Option<Outcome>) -> Self {
+        match outcome {
+            Some(outcome) => match outcome.winner() {
+                Some(winner) => RecordResult::Win(winner),
+                None => RecordResult::Draw,
+            },
+            None => RecordResult::Unfinished,
+        }
+    }
+
+    pub fn token(self) -> &'static str {
+        match self {
+            RecordResult::Win(XorZ::X) => "1-0",
+            RecordResult::Win(XorZ::Z) => "0-1",
+            RecordResult::Draw => "1/2-1/2",
+            RecordResult::Unfinished => "*",
+        }
+    }
+
+    fn from_token(token: &str) -> Option<Self> {
+        match token {
+            "1-0" => Some(RecordResult::Win(XorZ::X)),
+            "0-1" => Some(RecordResult::Win(XorZ::Z)),
+            "1/2-1/2" => Some(RecordResult::Draw),
+            "*" => Some(RecordResult::Unfinished),
+            _ => None,
+        }
+    }
+}
+
+/// A game with its tag pairs, moves and result.
+///
+/// The result is kept apart from the other tags and written as the
+/// `Result` tag as well as at the end of the moves.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GameRecord {
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<RecordedMove>,
+    pub result: RecordResult,
+}
+
+impl GameRecord {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Replaces the tag's value, or adds the tag.
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn push_move(&mut self, cell: CellCoord) {
+        self.moves.push(RecordedMove::new(cell));
+    }
+
+    /// Parses a record, replaying its moves to make sure they are legal
+    /// and agree with the result.
+    pub fn read(text: &str) -> Result<Self, RecordError> {
+        let mut reader = Reader::new(text);
+        let mut record = GameRecord::new();
+        let mut game: Option<Game> = None;
+        let mut result_tag = None;
+        let mut result = None;
+
+        loop {
+            reader.skip_whitespace();
+
+            let (line, column) = (reader.line, reader.column);
+            let c = match reader.peek() {
+                Some(c) => c,
+                None => break,
+            };
+
+            if result.is_some() {
+                return Err(RecordError::syntax(line, column, "text after the result"));
+            }
+
+            match c {
+                '[' => {
+                    if game.is_some() {
+                        return Err(RecordError::syntax(
+                            line,
+                            column,
+                            "tag after the first move",
+                        ));
+                    }
+
+                    let (name, value) = reader.tag()?;
+
+                    check_tag(&name, &value, line, column)?;
+
+                    if name == RESULT {
+                        result_tag = Some(value);
+                    } else {
+                        record.tags.push((name, value));
+                    }
+                }
+                '{' => {
+                    let comment = reader.comment()?;
+
+                    match record.moves.last_mut() {
+                        Some(last) => last.comment = Some(comment),
+                        None => {
+                            return Err(RecordError::syntax(
+                                line,
+                                column,
+                                "comment before the first move",
+                            ))
+                        }
+                    }
+                }
+                _ => {
+                    let token = reader.token();
+
+                    if is_move_number(&token) {
+                        continue;
+                    }
+
+                    if let Some(r) = RecordResult::from_token(&token) {
+                        result = Some((r, line, column));
+                        continue;
+                    }
+
+                    let recorded = parse_move(&token)
+                        .ok_or_else(|| RecordError::syntax(line, column, "invalid move"))?;
+
+                    // tags have been checked, so there is a valid starting position
+                    let game = game.get_or_insert_with(|| record.new_game().unwrap());
+                    let (x, y) = recorded.cell;
+
+                    if let Err(error) = game.mark(x, y) {
+                        return Err(RecordError::IllegalMove {
+                            line,
+                            column,
+                            reason: error.to_string(),
+                        });
+                    }
+
+                    record.moves.push(recorded);
+                }
+            }
+        }
+
+        let (result, line, column) = result
+            .ok_or_else(|| RecordError::syntax(reader.line, reader.column, "missing result"))?;
+
+        let outcome = game
+            .unwrap_or_else(|| record.new_game().unwrap())
+            .get_outcome();
+
+        let tag_agrees = result_tag.is_none_or(|tag| tag == result.token());
+        // a game that did not end on the board may still have been
+        // resigned, lost on time or drawn by agreement
+        let board_agrees = outcome.is_none() || RecordResult::of(outcome) == result;
+
+        if !(tag_agrees && board_agrees) {
+            return Err(RecordError::ResultMismatch { line, column });
+        }
+
+        record.result = result;
+
+        Ok(record)
+    }
+
+    /// Plays all moves of the record.
+    pub fn replay(&self) -> Result<Game, Error> {
+        let mut game = self.new_game()?;
+
+        for m in &self.moves {
+            game.mark(m.cell.0, m.cell.1)?;
+        }
+
+        Ok(game)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn write_to<W: Write>(&self, out: &mut W) -> Result<(), Error> {
+        write!(out, "{}", self)?;
+
+        Ok(())
+    }
+
+    /// The game before any moves.
+    pub(crate) fn new_game(&self) -> Result<Game, SetupError> {
+        if let Some(position) = self.tag(POSITION) {
+            return read_position(position);
+        }
+
+        let starting = match self.tag(START) {
+            Some("0") => XorZ::Z,
+            _ => XorZ::X,
+        };
+
+        GameBuilder::new().with_starting_player(starting).build()
+    }
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, value) in &self.tags {
+            writeln!(f, "[{} \"{}\"]", name, escape(value))?;
+        }
+        writeln!(f, "[{} \"{}\"]", RESULT, self.result.token())?;
+        writeln!(f)?;
+
+        let mut tokens = Vec::new();
+
+        for (i, m) in self.moves.iter().enumerate() {
+            if i % 2 == 0 {
+                tokens.push(format!("{}.", i / 2 + 1));
+            }
+
+            let annotation = m.annotation.map_or("", Annotation::symbol);
+            tokens.push(format!("{}{}", cell_name(m.cell), annotation));
+
+            if let Some(comment) = &m.comment {
+                tokens.push(format!("{{{}}}", escape_comment(comment)));
+            }
+        }
+
+        tokens.push(self.result.token().to_string());
+
+        let mut width = 0;
+
+        for token in tokens {
+            if width > 0 && width + 1 + token.len() > LINE_WIDTH {
+                writeln!(f)?;
+                width = 0;
+            } else if width > 0 {
+                write!(f, " ")?;
+                width += 1;
+            }
+
+            write!(f, "{}", token)?;
+            width += token.len();
+        }
+
+        writeln!(f)
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_comment(comment: &str) -> String {
+    comment.replace('\\', "\\\\").replace('}', "\\}")
+}
+
+fn check_tag(name: &str, value: &str, line: usize, column: usize) -> Result<(), RecordError> {
+    let supported = match name {
+        VARIANT => value == "classic",
+        BOARD_SIZE => value == "3",
+        START => value == "X" || value == "0",
+        POSITION => read_position(value).is_ok(),
+        RESULT => RecordResult::from_token(value).is_some(),
+        _ => true,
+    };
+
+    if supported {
+        Ok(())
+    } else {
+        Err(RecordError::UnsupportedTag {
+            line,
+            column,
+            name: name.to_string(),
+            value: value.to_string(),
+        })
+    }
+}
+
+fn is_move_number(token: &str) -> bool {
+    let digits = token.trim_end_matches('.');
+
+    digits.len() < token.len() && !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+/// A cell name followed by an optional annotation.
+fn parse_move(token: &str) -> Option<RecordedMove> {
+    let split = token.find(['!', '?']).unwrap_or(token.len());
+    let (cell, annotation) = token.split_at(split);
+
+    let annotation = if annotation.is_empty() {
+        None
+    } else {
+        Some(Annotation::from_symbol(annotation)?)
+    };
+
+    Some(RecordedMove {
+        cell: parse_cell(cell)?,
+        annotation,
+        comment: None,
+    })
+}
+
+/// Characters of a record, keeping track of their position.
+struct Reader<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            chars: text.chars().peekable(),
+            line: 1,
+            column: 1,
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.next();
+        }
+    }
+
+    fn error(&self, message: &str) -> RecordError {
+        RecordError::syntax(self.line, self.column, message)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), RecordError> {
+        if self.peek() == Some(expected) {
+            self.next();
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", expected)))
+        }
+    }
+
+    /// `[Name "value"]`
+    fn tag(&mut self) -> Result<(String, String), RecordError> {
+        self.expect('[')?;
+
+        let mut name = String::new();
+        while let Some(c) = self.peek().filter(char::is_ascii_alphanumeric) {
+            name.push(c);
+            self.next();
+        }
+
+        if name.is_empty() {
+            return Err(self.error("expected a tag name"));
+        }
+
+        self.skip_whitespace();
+        self.expect('"')?;
+
+        let mut value = String::new();
+        loop {
+            match self.next() {
+                Some('"') => break,
+                Some('\\') => match self.next() {
+                    Some(c @ '"') | Some(c @ '\\') => value.push(c),
+                    _ => return Err(self.error("invalid escape")),
+                },
+                Some('\n') | None => return Err(self.error("unterminated tag value")),
+                Some(c) => value.push(c),
+            }
+        }
+
+        self.skip_whitespace();
+        self.expect(']')?;
+
+        Ok((name, value))
+    }
+
+    /// `{text}`
+    fn comment(&mut self) -> Result<String, RecordError> {
+        self.expect('{')?;
+
+        let mut comment = String::new();
+        loop {
+            match self.next() {
+                Some('}') => return Ok(comment),
+                Some('\\') => match self.next() {
+                    Some(c @ '}') | Some(c @ '\\') => comment.push(c),
+                    _ => return Err(self.error("invalid escape")),
+                },
+                Some(c) => comment.push(c),
+                None => return Err(self.error("unterminated comment")),
+            }
+        }
+    }
+
+    /// Everything up to the next whitespace, tag or comment.
+    fn token(&mut self) -> String {
+        let mut token = String::new();
+
+        while let Some(c) = self
+            .peek()
+            .filter(|c| !c.is_whitespace() && !"[]{}".contains(*c))
+        {
+            token.push(c);
+            self.next();
+        }
+
+        // a lone bracket is not a token; consume it so it gets reported
+        if token.is_empty() {
+            token.extend(self.next());
+        }
+
+        token
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const RECORD: &str = r#"[PlayerX "Alice"]
+[Player0 "Bob \"the builder\""]
+[Date "2019.05.01"]
+[TimeControl "60+1"]
+[Result "1-0"]
+
+1. b2 a1 2. c1 {threatens a3} b1? 3. a3! 1-0
+"#;
+
+    #[test]
+    fn read_and_write() {
+        let record = GameRecord::read(RECORD).unwrap();
+
+        assert_eq!(record.tag(PLAYER_Z), Some("Bob \"the builder\""));
+        assert_eq!(record.tag(RESULT), None);
+        assert_eq!(record.result, RecordResult::Win(XorZ::X));
+        assert_eq!(record.moves.len(), 5);
+        assert_eq!(record.moves[0].cell, (1, 1));
+        assert_eq!(record.moves[2].comment.as_deref(), Some("threatens a3"));
+        assert_eq!(record.moves[3].annotation, Some(Annotation::Mistake));
+        assert_eq!(record.moves[4].annotation, Some(Annotation::Good));
+
+        assert_eq!(record.to_string(), RECORD);
+        assert_eq!(
+            record.replay().unwrap().get_outcome().unwrap().winner(),
+            Some(XorZ::X)
+        );
+    }
+
+    #[test]
+    fn comments_with_braces() {
+        let mut record = GameRecord::read("1. b2 {center} *").unwrap();
+        record.moves[0].comment = Some("{a} or \\{b}} \\".to_string());
+
+        let text = record.to_string();
+
+        assert!(text.contains(r"{{a\} or \\{b\}\} \\}"), "{}", text);
+        assert_eq!(GameRecord::read(&text).unwrap(), record);
+
+        assert!(GameRecord::read(r"1. b2 {a \b} *").is_err());
+    }
+
+    #[test]
+    fn unfinished_and_resigned() {
+        let record = GameRecord::read("[Start \"0\"]\n1. b2 *").unwrap();
+
+        assert_eq!(record.replay().unwrap().turn(), XorZ::X);
+
+        // 0 resigned after the first move
+        assert!(GameRecord::read("1. b2 1-0").is_ok());
+    }
+
+    #[test]
+    fn positions() {
+        let game = read_position("X0./.X./... 0").unwrap();
+
+        assert_eq!(game.turn(), XorZ::Z);
+        assert_eq!(
+            game.board.cell(cell_index(1, 0)),
+            CellState::Marked(XorZ::Z)
+        );
+        assert_eq!(position_name(&game), "X0./.X./... 0");
+
+        for invalid in &["X0./.X./...", "X0./.X. X", "X0./.X./..Y X", "XXX/.../... 0"] {
+            assert!(read_position(invalid).is_err(), "{}", invalid);
+        }
+
+        // moves continue from the position
+        let record = GameRecord::read("[Position \"X0./.X./... 0\"]\n1. c3 *").unwrap();
+        let game = record.replay().unwrap();
+
+        assert_eq!(position_name(&game), "X0./.X./..0 X");
+
+        let blocked = read_position("X#./.0./... X").unwrap();
+
+        assert!(blocked.is_blocked(1, 0));
+        assert_eq!(position_name(&blocked), "X#./.0./... X");
+    }
+
+    #[test]
+    fn cell_names() {
+        for x in 0..3 {
+            for y in 0..3 {
+                assert_eq!(parse_cell(&cell_name((x, y))), Some((x, y)));
+            }
+        }
+
+        assert_eq!(parse_cell("d1"), None);
+        assert_eq!(parse_cell("a0"), None);
+        assert_eq!(parse_cell("a11"), None);
+    }
+
+    #[test]
+    fn errors() {
+        let position = |text: &str| match GameRecord::read(text).unwrap_err() {
+            RecordError::Syntax { line, column, .. }
+            | RecordError::IllegalMove { line, column, .. }
+            | RecordError::UnsupportedTag { line, column, .. }
+            | RecordError::ResultMismatch { line, column } => (line, column),
+        };
+
+        // the cell is already marked
+        assert_eq!(position("[Date \"today\"]\n\n1. b2 b2 *"), (3, 7));
+        assert_eq!(position("1. b2 d4 *"), (1, 7));
+        assert_eq!(position("[BoardSize \"4\"]\n*"), (1, 1));
+        assert_eq!(position("{too early} 1. b2 *"), (1, 1));
+        assert_eq!(position("1. b2 {never closed *"), (1, 22));
+        assert_eq!(position("1. b2"), (1, 6));
+        assert_eq!(position("1. b2 * c1"), (1, 9));
+        assert_eq!(position("1. b2 a1 2. c1 b1 3. a3 0-1"), (1, 25));
+        assert_eq!(position("[Result \"0-1\"]\n1-0"), (2, 1));
+    }
+}
@@ -0,0 +1,371 @@
+//! Series of games between the same two players.
+use alloc::sync::Arc;
+
+use super::clock::{TimeControl, TimeSource};
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MatchFormat {
+    /// The first player to win this many games wins the match.
+    FirstTo(u32),
+    /// At most this many games; the player with more wins takes the match.
+    BestOf(u32),
+}
+
+/// One of the two players of a match. The first player plays X and
+/// the second plays 0 in every game; who starts alternates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Seat {
+    First,
+    Second,
+}
+
+impl Seat {
+    pub fn other(self) -> Self {
+        match self {
+            Seat::First => Seat::Second,
+            Seat::Second => Seat::First,
+        }
+    }
+
+    pub fn side(self) -> XorZ {
+        match self {
+            Seat::First => XorZ::X,
+            Seat::Second => XorZ::Z,
+        }
+    }
+
+    pub fn of_side(side: XorZ) -> Self {
+        match side {
+            XorZ::X => Seat::First,
+            XorZ::Z => Seat::Second,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MatchResult {
+    Won(Seat),
+    Drawn,
+}
+
+#[derive(Debug)]
+pub struct Match {
+    format: MatchFormat,
+    wins: [u32; 2],
+    draws: u32,
+    /// outcomes of all finished games, in order
+    history: Vec<Outcome>,
+    /// a clock that never runs, copied into every game
+    clock: Option<Clock>,
+    game: Game,
+}
+
+impl Match {
+    /// A match whose games are played with a fresh clock each, if
+    /// `clock` is given. Fails for a target of 0 games.
+    pub fn new(
+        format: MatchFormat,
+        clock: Option<(TimeControl, Arc<dyn TimeSource + Send + Sync>)>,
+    ) -> Result<Self, MatchError> {
+        if let MatchFormat::FirstTo(0) | MatchFormat::BestOf(0) = format {
+            return Err(MatchError::NoGames);
+        }
+
+        let clock = clock.map(|(control, source)| Clock::new(control, source));
+
+        Ok(Self {
+            format,
+            wins: [0; 2],
+            draws: 0,
+            history: Vec::new(),
+            game: Self::game_number(0, clock.as_ref()),
+            clock,
+        })
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Marks a cell in the current game, recording its outcome.
+    pub fn mark(&mut self, x: usize, y: usize) -> MarkResult {
+        let outcome = self.game.mark(x, y)?;
+
+        if let Some(outcome) = outcome {
+            self.record(outcome);
+        }
+
+        Ok(outcome)
+    }
+
+    /// The player gives up the current game.
+    pub fn resign(&mut self, seat: Seat) -> Result<Outcome, ActionError> {
+        let outcome = self.game.resign(seat.side())?;
+        self.record(outcome);
+
+        Ok(outcome)
+    }
+
+    /// The player left; the opponent wins the current game.
+    pub fn disconnect(&mut self, seat: Seat) -> Result<Outcome, ActionError> {
+        let outcome = self.game.disconnect(seat.side())?;
+        self.record(outcome);
+
+        Ok(outcome)
+    }
+
+    pub fn offer_draw(&mut self, seat: Seat) -> Result<(), ActionError> {
+        self.game.offer_draw(seat.side())
+    }
+
+    pub fn accept_draw(&mut self, seat: Seat) -> Result<Outcome, ActionError> {
+        let outcome = self.game.accept_draw(seat.side())?;
+        self.record(outcome);
+
+        Ok(outcome)
+    }
+
+    /// Ends the current game if a player ran out of time.
+    pub fn check_time(&mut self) -> Option<Outcome> {
+        let outcome = self.game.check_time()?;
+        self.record(outcome);
+
+        Some(outcome)
+    }
+
+    /// Starts the next game once the current one is over.
+    pub fn next_game(&mut self) -> Result<(), MatchError> {
+        if !self.game.ended() {
+            return Err(MatchError::GameInProgress);
+        }
+
+        if self.result().is_some() {
+            return Err(MatchError::MatchDecided);
+        }
+
+        self.game = Self::game_number(self.history.len(), self.clock.as_ref());
+
+        Ok(())
+    }
+
+    /// `None` while the match is still being played.
+    pub fn result(&self) -> Option<MatchResult> {
+        let (first, second) = (self.wins(Seat::First), self.wins(Seat::Second));
+
+        let leader = if first > second {
+            MatchResult::Won(Seat::First)
+        } else if second > first {
+            MatchResult::Won(Seat::Second)
+        } else {
+            MatchResult::Drawn
+        };
+
+        match self.format {
+            MatchFormat::FirstTo(n) if first.max(second) >= n => Some(leader),
+            MatchFormat::FirstTo(_) => None,
+            MatchFormat::BestOf(n) => {
+                let remaining = n.saturating_sub(self.games_played());
+
+                // the trailing player cannot catch up anymore
+                if remaining == 0 || first.max(second) > first.min(second) + remaining {
+                    Some(leader)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    pub fn wins(&self, seat: Seat) -> u32 {
+        self.wins[seat as usize]
+    }
+
+    pub fn draws(&self) -> u32 {
+        self.draws
+    }
+
+    pub fn games_played(&self) -> u32 {
+        self.history.len() as u32
+    }
+
+    pub fn history(&self) -> &[Outcome] {
+        &self.history
+    }
+
+    pub fn format(&self) -> MatchFormat {
+        self.format
+    }
+
+    /// Games alternate between X and 0 starting, beginning with X.
+    fn game_number(index: usize, clock: Option<&Clock>) -> Game {
+        let mut builder = GameBuilder::new().with_alternating_start(XorZ::X, index);
+
+        if let Some(clock) = clock {
+            builder = builder.with_clock(clock.control(), clock.source());
+        }
+
+        builder.build().unwrap()
+    }
+
+    fn record(&mut self, outcome: Outcome) {
+        match outcome.winner() {
+            Some(side) => self.wins[Seat::of_side(side) as usize] += 1,
+            None => self.draws += 1,
+        }
+
+        self.history.push(outcome);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::time::Duration;
+
+    use super::*;
+    use crate::clock::ManualTime;
+    use Seat::*;
+
+    #[test]
+    fn alternating_starts() {
+        let mut m = Match::new(MatchFormat::FirstTo(3), None).unwrap();
+
+        assert_eq!(m.game().turn(), XorZ::X);
+
+        m.resign(Second).unwrap();
+        m.next_game().unwrap();
+
+        assert_eq!(m.game().turn(), XorZ::Z);
+
+        m.offer_draw(First).unwrap();
+        m.accept_draw(Second).unwrap();
+        m.next_game().unwrap();
+
+        assert_eq!(m.game().turn(), XorZ::X);
+        assert_eq!((m.wins(First), m.draws()), (1, 1));
+    }
+
+    #[test]
+    fn first_to() {
+        let mut m = Match::new(MatchFormat::FirstTo(2), None).unwrap();
+
+        play_draw(&mut m);
+        m.next_game().unwrap();
+
+        win_as_first(&mut m);
+        assert_eq!(m.result(), None);
+        m.next_game().unwrap();
+
+        m.resign(Second).unwrap();
+
+        assert_eq!(m.result(), Some(MatchResult::Won(First)));
+        assert_eq!(m.draws(), 1);
+        assert!(m.next_game().is_err());
+    }
+
+    #[test]
+    fn best_of() {
+        let mut m = Match::new(MatchFormat::BestOf(3), None).unwrap();
+
+        m.resign(First).unwrap();
+        m.next_game().unwrap();
+        m.resign(First).unwrap();
+
+        // the first player cannot win 2 more games out of 1
+        assert_eq!(m.result(), Some(MatchResult::Won(Second)));
+
+        m = Match::new(MatchFormat::BestOf(2), None).unwrap();
+
+        m.resign(First).unwrap();
+        m.next_game().unwrap();
+
+        assert_eq!(m.result(), None);
+
+        m.resign(Second).unwrap();
+
+        assert_eq!(m.result(), Some(MatchResult::Drawn));
+    }
+
+    #[test]
+    fn zero_games() {
+        for format in [MatchFormat::FirstTo(0), MatchFormat::BestOf(0)] {
+            assert!(matches!(Match::new(format, None), Err(MatchError::NoGames)));
+        }
+    }
+
+    #[test]
+    fn game_in_progress() {
+        let mut m = Match::new(MatchFormat::BestOf(3), None).unwrap();
+
+        assert!(m.next_game().is_err());
+
+        m.resign(First).unwrap();
+
+        assert!(m.resign(Second).is_err());
+    }
+
+    #[test]
+    fn time_and_disconnects() {
+        let time = ManualTime::new();
+        let control = TimeControl::SuddenDeath {
+            base: Duration::from_secs(10),
+        };
+        let mut m = Match::new(
+            MatchFormat::FirstTo(2),
+            Some((control, Arc::new(time.clone()))),
+        )
+        .unwrap();
+
+        m.mark(1, 1).unwrap();
+        time.advance(Duration::from_secs(11));
+
+        // 0 ran out of time
+        assert_eq!(
+            m.check_time(),
+            Some(Outcome::Win(XorZ::X, WinReason::Timeout))
+        );
+        assert_eq!(m.history(), &[Outcome::Win(XorZ::X, WinReason::Timeout)]);
+        assert_eq!(m.wins(First), 1);
+
+        m.next_game().unwrap();
+
+        // every game gets a fresh clock
+        let clock = m.game().clock().unwrap();
+        assert_eq!(clock.remaining(XorZ::Z), Duration::from_secs(10));
+        assert_eq!(clock.remaining(XorZ::X), Duration::from_secs(10));
+
+        m.disconnect(Second).unwrap();
+
+        assert_eq!(m.result(), Some(MatchResult::Won(First)));
+        assert_eq!(m.history()[1], Outcome::Win(XorZ::X, WinReason::Disconnect));
+    }
+
+    fn play_draw(m: &mut Match) {
+        let moves = [
+            (0, 0),
+            (1, 1),
+            (0, 1),
+            (0, 2),
+            (2, 0),
+            (1, 0),
+            (1, 2),
+            (2, 2),
+            (2, 1),
+        ];
+
+        for (x, y) in moves.iter() {
+            m.mark(*x, *y).unwrap();
+        }
+    }
+
+    /// The first player (X) wins whether or not it starts.
+    fn win_as_first(m: &mut Match) {
+        if m.game().turn() == XorZ::Z {
+            m.mark(2, 2).unwrap();
+        }
+
+        for (x, y) in &[(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)] {
+            m.mark(*x, *y).unwrap();
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! Perfect play search over the whole remaining game tree.
+use alloc::collections::BTreeMap;
+
+use super::*;
+
+/// Result of a position when both players play perfectly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    Draw,
+    Win(XorZ),
+}
+
+/// Value of the game's current position, with the player
+/// whose turn it is to move next.
+pub fn value(game: &Game) -> Value {
+    Solver::new().value(&game.searchable())
+}
+
+/// All moves that keep the best value for the player to move.
+///
+/// Returns no moves if the game has ended.
+pub fn best_moves(game: &Game) -> Vec<CellCoord> {
+    let game = game.searchable();
+    let mut solver = Solver::new();
+
+    let scored: Vec<_> = game
+        .legal_moves()
+        .map(|(x, y)| {
+            let mut next = game.clone();
+            next.mark(x, y).unwrap();
+
+            ((x, y), score(solver.value(&next), game.turn_of))
+        })
+        .collect();
+
+    let best = scored.iter().map(|(_, s)| *s).max();
+
+    scored
+        .into_iter()
+        .filter(|(_, s)| Some(*s) == best)
+        .map(|(coord, _)| coord)
+        .collect()
+}
+
+/// How good is `value` for `player`?
+fn score(value: Value, player: XorZ) -> i8 {
+    match value {
+        Value::Draw => 0,
+        Value::Win(winner) if winner == player => 1,
+        Value::Win(_) => -1,
+    }
+}
+
+struct Solver {
+    known: BTreeMap<(ClassicBoard, XorZ), Value>,
+}
+
+impl Solver {
+    fn new() -> Self {
+        Self {
+            known: BTreeMap::new(),
+        }
+    }
+
+    fn value(&mut self, game: &Game) -> Value {
+        if let Some(outcome) = game.get_outcome() {
+            return match outcome.winner() {
+                Some(winner) => Value::Win(winner),
+                None => Value::Draw,
+            };
+        }
+
+        let key = (game.board, game.turn_of);
+
+        if let Some(value) = self.known.get(&key) {
+            return *value;
+        }
+
+        let player = game.turn_of;
+        let mut best = None;
+
+        for (x, y) in game.legal_moves() {
+            let mut next = game.clone();
+            next.mark(x, y).unwrap();
+
+            let value = self.value(&next);
+
+            if best.is_none_or(|b| score(value, player) > score(b, player)) {
+                best = Some(value);
+            }
+
+            // cannot do better than winning
+            if value == Value::Win(player) {
+                break;
+            }
+        }
+
+        let value = best.unwrap_or(Value::Draw);
+        self.known.insert(key, value);
+
+        value
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn empty_board_is_a_draw() {
+        assert_eq!(value(&Game::new()), Value::Draw);
+    }
+
+    #[test]
+    fn finds_the_winning_move() {
+        let mut g = Game::new();
+
+        for (x, y) in &[(0, 0), (1, 0), (0, 1), (1, 1)] {
+            g.mark(*x, *y).unwrap();
+        }
+
+        assert_eq!(value(&g), Value::Win(XorZ::X));
+        assert_eq!(best_moves(&g), vec![(0, 2)]);
+    }
+
+    #[test]
+    fn finished_game() {
+        let mut g = Game::new();
+
+        for (x, y) in &[(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)] {
+            g.mark(*x, *y).unwrap();
+        }
+
+        assert_eq!(value(&g), Value::Win(XorZ::X));
+        assert!(best_moves(&g).is_empty());
+    }
+}
@@ -0,0 +1,134 @@
+//! Statistics over the complete game tree.
+use alloc::collections::BTreeSet;
+use alloc::vec;
+
+use super::*;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TreeStats {
+    /// distinct move sequences that end the game
+    pub games: u64,
+    /// distinct positions, including the starting and finished ones
+    pub positions: u64,
+    /// games won by X, indexed by the number of moves played
+    pub x_wins: Vec<u64>,
+    /// games won by 0, indexed by the number of moves played
+    pub z_wins: Vec<u64>,
+    /// drawn games, indexed by the number of moves played
+    pub draws: Vec<u64>,
+    /// distinct positions after each number of moves
+    pub positions_per_depth: Vec<u64>,
+}
+
+impl TreeStats {
+    pub fn total_x_wins(&self) -> u64 {
+        self.x_wins.iter().sum()
+    }
+
+    pub fn total_z_wins(&self) -> u64 {
+        self.z_wins.iter().sum()
+    }
+
+    pub fn total_draws(&self) -> u64 {
+        self.draws.iter().sum()
+    }
+}
+
+/// Plays every possible continuation of `game`.
+pub fn enumerate(game: &Game) -> TreeStats {
+    let depths = game.empty_cells().count() + 1;
+
+    let mut walker = Walker {
+        stats: TreeStats {
+            x_wins: vec![0; depths],
+            z_wins: vec![0; depths],
+            draws: vec![0; depths],
+            positions_per_depth: vec![0; depths],
+            ..TreeStats::default()
+        },
+        seen: BTreeSet::new(),
+    };
+
+    walker.walk(&game.searchable(), 0);
+
+    walker.stats
+}
+
+struct Walker {
+    stats: TreeStats,
+    seen: BTreeSet<(ClassicBoard, XorZ)>,
+}
+
+impl Walker {
+    fn walk(&mut self, game: &Game, depth: usize) {
+        if self.seen.insert((game.board, game.turn_of)) {
+            self.stats.positions += 1;
+            self.stats.positions_per_depth[depth] += 1;
+        }
+
+        match game.get_outcome() {
+            Some(outcome) => {
+                let by_length = match outcome.winner() {
+                    Some(XorZ::X) => &mut self.stats.x_wins,
+                    Some(XorZ::Z) => &mut self.stats.z_wins,
+                    None => &mut self.stats.draws,
+                };
+
+                by_length[depth] += 1;
+                self.stats.games += 1;
+            }
+            None => {
+                for (x, y) in game.legal_moves() {
+                    let mut next = game.clone();
+                    next.mark(x, y).unwrap();
+
+                    self.walk(&next, depth + 1);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn published_numbers() {
+        let stats = enumerate(&Game::new());
+
+        assert_eq!(stats.games, 255_168);
+        assert_eq!(stats.positions, 5_478);
+
+        assert_eq!(stats.total_x_wins(), 131_184);
+        assert_eq!(stats.total_z_wins(), 77_904);
+        assert_eq!(stats.total_draws(), 46_080);
+
+        assert_eq!(
+            stats.x_wins,
+            vec![0, 0, 0, 0, 0, 1_440, 0, 47_952, 0, 81_792]
+        );
+        assert_eq!(stats.z_wins, vec![0, 0, 0, 0, 0, 0, 5_328, 0, 72_576, 0]);
+        assert_eq!(stats.draws[9], 46_080);
+
+        assert_eq!(
+            stats.positions_per_depth,
+            vec![1, 9, 72, 252, 756, 1_260, 1_520, 1_140, 390, 78]
+        );
+    }
+
+    #[test]
+    fn from_a_started_game() {
+        let mut g = Game::new();
+
+        for (x, y) in &[(0, 0), (1, 0), (0, 1), (1, 1)] {
+            g.mark(*x, *y).unwrap();
+        }
+
+        let stats = enumerate(&g);
+
+        // X can win right away, or play on in 4 other ways
+        assert_eq!(stats.x_wins[1], 1);
+        assert_eq!(stats.positions_per_depth[1], 5);
+    }
+}
@@ -0,0 +1,96 @@
+//! The 8 symmetries of a square board, and helpers working on the
+//! bit masks of a classic `Bitboard`.
+//!
+//! This module is also compiled into the build script, so it must
+//! not depend on anything else in the crate.
+
+/// Number of rotations and reflections of the board.
+pub const COUNT: usize = 8;
+
+/// Number of distinct ways to fill the 9 cells with empty, X or 0.
+pub const POSITIONS: usize = 19_683;
+
+/// The symmetry that undoes each symmetry.
+const INVERSES: [usize; COUNT] = [0, 3, 2, 1, 4, 5, 6, 7];
+
+/// Cell that `index` of a board with rows of length `size` is moved
+/// to by `symmetry`: quarter turns for the first half, a mirror
+/// image followed by quarter turns for the second.
+pub fn transform(size: usize, symmetry: usize, index: usize) -> usize {
+    let last = size - 1;
+    let (mut x, mut y) = (index % size, index / size);
+
+    if symmetry >= 4 {
+        x = last - x;
+    }
+
+    for _ in 0..symmetry % 4 {
+        let turned = (last - y, x);
+        x = turned.0;
+        y = turned.1;
+    }
+
+    y * size + x
+}
+
+/// Moves the bits of a classic board's `mask` according to
+/// `symmetry`.
+pub fn apply(symmetry: usize, mask: u16) -> u16 {
+    (0..9)
+        .filter(|from| mask & (1 << from) != 0)
+        .fold(0, |moved, from| moved | (1 << transform(3, symmetry, from)))
+}
+
+pub fn inverse(symmetry: usize) -> usize {
+    INVERSES[symmetry]
+}
+
+/// Unique number of a position: the cells as digits of a base 3
+/// number, 1 for X and 2 for 0.
+pub fn index(x: u16, z: u16) -> usize {
+    (0..9).rev().fold(0, |index, cell| {
+        let digit = if x & (1 << cell) != 0 {
+            1
+        } else if z & (1 << cell) != 0 {
+            2
+        } else {
+            0
+        };
+
+        index * 3 + digit
+    })
+}
+
+/// The symmetric position with the lowest index, and the symmetry
+/// that turns the given position into it.
+pub fn canonical(x: u16, z: u16) -> (u16, u16, usize) {
+    (0..COUNT)
+        .map(|s| (apply(s, x), apply(s, z), s))
+        .min_by_key(|&(x, z, _)| index(x, z))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn inverses() {
+        let mask = 0b000_010_011;
+
+        for s in 0..COUNT {
+            assert_eq!(apply(inverse(s), apply(s, mask)), mask);
+        }
+    }
+
+    #[test]
+    fn corners_are_equivalent() {
+        let corners = [0, 2, 6, 8];
+        let canonical: Vec<_> = corners.iter().map(|c| canonical(1 << c, 0)).collect();
+
+        for (x, z, s) in canonical.iter() {
+            assert_eq!((*x, *z), (canonical[0].0, 0));
+            assert!(*s < COUNT);
+        }
+    }
+}
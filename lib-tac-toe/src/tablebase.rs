@@ -0,0 +1,130 @@
+//! Perfect play lookup table for the classic board, generated at
+//! build time.
+//!
+//! The table covers every position that can be reached when X moves
+//! first, so the player to move is known from the number of marks.
+use super::solver::Value;
+use super::*;
+
+include!(concat!(env!("OUT_DIR"), "/tablebase.rs"));
+
+const BEST_MOVES: u16 = 0b111_111_111;
+
+/// Perfect play information about a position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Entry {
+    pub value: Value,
+    /// moves until the game ends, when the winner wins as fast as
+    /// possible and the loser delays it as long as possible
+    pub distance: u8,
+    /// `ClassicBoard` bits of the moves that keep `value`
+    best_moves: u16,
+}
+
+impl Entry {
+    /// Moves that keep the value of the position; none if the game
+    /// has ended.
+    pub fn best_moves(&self) -> impl Iterator<Item = CellCoord> {
+        mask_cells(self.best_moves)
+    }
+
+    fn decode(entry: u16, symmetry: usize) -> Option<Self> {
+        let value = match (entry >> 9) & 0b11 {
+            1 => Value::Draw,
+            2 => Value::Win(XorZ::X),
+            3 => Value::Win(XorZ::Z),
+            _ => return None,
+        };
+
+        Some(Self {
+            value,
+            distance: (entry >> 11) as u8,
+            best_moves: symmetry::apply(symmetry::inverse(symmetry), entry & BEST_MOVES),
+        })
+    }
+}
+
+/// Looks up a position of a game where X moved first.
+///
+/// Returns `None` for positions that cannot be reached in such a game,
+/// and for positions with blocked cells.
+pub fn lookup(state: &BoardState) -> Option<Entry> {
+    let blocked = state
+        .iter()
+        .flatten()
+        .any(|cell| *cell == CellState::Blocked);
+
+    if blocked {
+        return None;
+    }
+
+    lookup_board(&ClassicBoard::from_board_state(state))
+}
+
+pub fn lookup_board(board: &ClassicBoard) -> Option<Entry> {
+    let (x, z) = (board.marks(XorZ::X), board.marks(XorZ::Z));
+    let (x, z, symmetry) = symmetry::canonical(x, z);
+
+    Entry::decode(TABLE[symmetry::index(x, z)], symmetry)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn empty_board() {
+        let entry = lookup(Game::new().board_state()).unwrap();
+
+        assert_eq!(entry.value, Value::Draw);
+        assert_eq!(entry.distance, 9);
+        assert_eq!(entry.best_moves().count(), 9);
+    }
+
+    #[test]
+    fn unreachable() {
+        let mut board = ClassicBoard::new();
+        board.place(0, XorZ::Z);
+
+        assert_eq!(lookup_board(&board), None);
+    }
+
+    #[test]
+    fn blocked_cells() {
+        let game = GameBuilder::new().with_blocked_cell(1, 1).build().unwrap();
+
+        assert_eq!(lookup(game.board_state()), None);
+    }
+
+    #[test]
+    fn agrees_with_solver() {
+        check_all(&Game::new(), &mut HashSet::new());
+    }
+
+    fn check_all(game: &Game, seen: &mut HashSet<ClassicBoard>) {
+        if !seen.insert(*game.bitboard()) {
+            return;
+        }
+
+        let entry = lookup_board(game.bitboard()).unwrap();
+
+        assert_eq!(entry.value, solver::value(game));
+
+        let mut best: Vec<_> = entry.best_moves().collect();
+        let mut solved = solver::best_moves(game);
+
+        best.sort();
+        solved.sort();
+
+        assert_eq!(best, solved);
+
+        for (x, y) in game.legal_moves() {
+            let mut next = game.clone();
+            next.mark(x, y).unwrap();
+
+            check_all(&next, seen);
+        }
+    }
+}
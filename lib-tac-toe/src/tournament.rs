@@ -0,0 +1,322 @@
+//! Round-robin tournaments between `Player` implementations.
+use std::f64::consts::{LN_10, PI};
+use std::fmt;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::player::{GameDriver, Player};
+use super::*;
+
+/// Creates a fresh player for every game, from the game's seed.
+pub type PlayerFactory = Box<dyn Fn(u64) -> Box<dyn Player>>;
+
+const INITIAL_RATING: f64 = 1500.0;
+const INITIAL_DEVIATION: f64 = 350.0;
+/// z value of a 95% confidence interval
+const CONFIDENCE_Z: f64 = 1.96;
+
+/// Glicko rating of an entrant.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rating {
+    pub rating: f64,
+    pub deviation: f64,
+}
+
+impl Rating {
+    /// Half the width of the 95% confidence interval.
+    pub fn margin(&self) -> f64 {
+        CONFIDENCE_Z * self.deviation
+    }
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Self {
+            rating: INITIAL_RATING,
+            deviation: INITIAL_DEVIATION,
+        }
+    }
+}
+
+/// Results of one entrant against another, from the first one's
+/// point of view.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PairScore {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl PairScore {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// A win counts 1 and a draw counts 1/2.
+    pub fn points(&self) -> f64 {
+        self.wins as f64 + self.draws as f64 / 2.0
+    }
+}
+
+struct Entrant {
+    name: String,
+    factory: PlayerFactory,
+}
+
+/// Plays every pair of entrants against each other, both as X and
+/// as 0, once per round.
+///
+/// Ratings are updated with the Glicko system, treating every round
+/// as a rating period. A player that fails to move or makes an
+/// illegal move forfeits the game.
+pub struct Tournament {
+    entrants: Vec<Entrant>,
+    rounds: usize,
+    seed: u64,
+}
+
+impl Tournament {
+    pub fn new(rounds: usize, seed: u64) -> Self {
+        Self {
+            entrants: Vec::new(),
+            rounds,
+            seed,
+        }
+    }
+
+    pub fn with_player<F, P>(mut self, name: &str, factory: F) -> Self
+    where
+        F: Fn(u64) -> P + 'static,
+        P: Player + 'static,
+    {
+        self.entrants.push(Entrant {
+            name: name.to_string(),
+            factory: Box::new(move |seed| Box::new(factory(seed))),
+        });
+
+        self
+    }
+
+    pub fn run(&self) -> Standings {
+        let count = self.entrants.len();
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut scores = vec![vec![PairScore::default(); count]; count];
+        let mut ratings = vec![Rating::default(); count];
+
+        // (X, 0) of every game in a round
+        let pairings: Vec<_> = (0..count)
+            .flat_map(|x| (0..count).filter(move |z| *z != x).map(move |z| (x, z)))
+            .collect();
+
+        for _ in 0..self.rounds {
+            // (player, opponent, score) of every game in the round
+            let mut results = Vec::new();
+
+            for &(x, z) in &pairings {
+                let score = match self.play(x, z, rng.gen()) {
+                    Some(XorZ::X) => 1.0,
+                    Some(XorZ::Z) => 0.0,
+                    None => 0.5,
+                };
+
+                record(&mut scores[x][z], score);
+                record(&mut scores[z][x], 1.0 - score);
+
+                results.push((x, z, score));
+                results.push((z, x, 1.0 - score));
+            }
+
+            ratings = (0..count)
+                .map(|player| {
+                    let games = results
+                        .iter()
+                        .filter(|(p, _, _)| *p == player)
+                        .map(|(_, opponent, score)| (ratings[*opponent], *score));
+
+                    glicko_update(ratings[player], games)
+                })
+                .collect();
+        }
+
+        Standings {
+            names: self.entrants.iter().map(|e| e.name.clone()).collect(),
+            scores,
+            ratings,
+        }
+    }
+
+    /// Plays one game with `Game` as the referee; returns the winner.
+    fn play(&self, x: usize, z: usize, seed: u64) -> Option<XorZ> {
+        let x_player = (self.entrants[x].factory)(seed);
+        let z_player = (self.entrants[z].factory)(seed.wrapping_add(1));
+
+        let mut driver = GameDriver::new(Game::new(), x_player, z_player);
+
+        match driver.run() {
+            Ok(outcome) => outcome.winner(),
+            Err(_) => Some(driver.game().turn().other()),
+        }
+    }
+}
+
+fn record(score: &mut PairScore, points: f64) {
+    if points == 1.0 {
+        score.wins += 1;
+    } else if points == 0.0 {
+        score.losses += 1;
+    } else {
+        score.draws += 1;
+    }
+}
+
+/// Rating after one rating period with the given games, each
+/// against an opponent's rating at the start of the period.
+fn glicko_update<I: Iterator<Item = (Rating, f64)>>(player: Rating, games: I) -> Rating {
+    let q = LN_10 / 400.0;
+    let g = |deviation: f64| 1.0 / (1.0 + 3.0 * (q * deviation / PI).powi(2)).sqrt();
+
+    let mut information = 0.0;
+    let mut improvement = 0.0;
+
+    for (opponent, score) in games {
+        let g = g(opponent.deviation);
+        let expected = 1.0 / (1.0 + 10f64.powf(-g * (player.rating - opponent.rating) / 400.0));
+
+        information += q * q * g * g * expected * (1.0 - expected);
+        improvement += g * (score - expected);
+    }
+
+    let precision = 1.0 / player.deviation.powi(2) + information;
+
+    Rating {
+        rating: player.rating + q / precision * improvement,
+        deviation: (1.0 / precision).sqrt(),
+    }
+}
+
+/// Final results of a tournament; prints as a crosstable.
+#[derive(Debug, Clone)]
+pub struct Standings {
+    names: Vec<String>,
+    /// `scores[a][b]`: results of a against b
+    scores: Vec<Vec<PairScore>>,
+    ratings: Vec<Rating>,
+}
+
+impl Standings {
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn rating(&self, player: usize) -> Rating {
+        self.ratings[player]
+    }
+
+    pub fn score(&self, player: usize, opponent: usize) -> PairScore {
+        self.scores[player][opponent]
+    }
+
+    /// Total points of a player against all opponents.
+    pub fn points(&self, player: usize) -> f64 {
+        self.scores[player].iter().map(PairScore::points).sum()
+    }
+
+    /// Indexes of the players, best rated first.
+    pub fn ranking(&self) -> Vec<usize> {
+        let mut ranking: Vec<_> = (0..self.names.len()).collect();
+
+        ranking.sort_by(|a, b| self.ratings[*b].rating.total_cmp(&self.ratings[*a].rating));
+
+        ranking
+    }
+}
+
+impl fmt::Display for Standings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ranking = self.ranking();
+        let width = self.names.iter().map(String::len).max().unwrap_or(0);
+
+        write!(
+            f,
+            "{:>2}  {:w$}  {:>11}  {:>6}",
+            "#",
+            "Player",
+            "Rating",
+            "Points",
+            w = width
+        )?;
+        for rank in 1..=ranking.len() {
+            write!(f, "  {:>6}", rank)?;
+        }
+        writeln!(f)?;
+
+        for (rank, &player) in ranking.iter().enumerate() {
+            let rating = self.ratings[player];
+
+            write!(
+                f,
+                "{:>2}  {:w$}  {:>4.0} ± {:>4.0}  {:>6.1}",
+                rank + 1,
+                self.names[player],
+                rating.rating,
+                rating.margin(),
+                self.points(player),
+                w = width
+            )?;
+
+            for &opponent in &ranking {
+                if opponent == player {
+                    write!(f, "  {:>6}", "-")?;
+                } else {
+                    write!(f, "  {:>6.1}", self.scores[player][opponent].points())?;
+                }
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::player::{PerfectPlayer, RandomPlayer};
+
+    fn tournament() -> Tournament {
+        Tournament::new(10, 3)
+            .with_player("perfect", PerfectPlayer::new)
+            .with_player("random", RandomPlayer::new)
+            .with_player("lazy", |_| player::QueuedPlayer::new())
+    }
+
+    #[test]
+    fn round_robin() {
+        let standings = tournament().run();
+
+        assert_eq!(standings.ranking(), vec![0, 1, 2]);
+
+        // every pair plays 2 games a round, one with each side
+        assert_eq!(standings.score(0, 1).games(), 20);
+        assert_eq!(standings.score(0, 1).losses, 0);
+
+        // a player without moves forfeits every game
+        assert_eq!(standings.points(2), 0.0);
+        assert_eq!(standings.score(1, 2).wins, 20);
+
+        let perfect = standings.rating(0);
+        let random = standings.rating(1);
+
+        assert!(perfect.rating - perfect.margin() > random.rating);
+        assert!(perfect.deviation < INITIAL_DEVIATION);
+    }
+
+    #[test]
+    fn seeded() {
+        let (a, b) = (tournament().run(), tournament().run());
+
+        assert_eq!(a.to_string(), b.to_string());
+        assert_eq!(a.to_string().lines().count(), 4);
+    }
+}
@@ -0,0 +1,265 @@
+//! Tabular Q-learning through self-play.
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use super::*;
+
+#[derive(Debug, Clone)]
+pub struct TrainConfig {
+    pub episodes: usize,
+    /// how far each update moves a value towards its target
+    pub learning_rate: f64,
+    /// weight of the opponent's best reply in the target
+    pub discount: f64,
+    /// chance of a random move instead of the best known one
+    pub epsilon: f64,
+    /// episodes between evaluations against the perfect player
+    pub eval_every: usize,
+    /// games played as each of X and 0 in an evaluation
+    pub eval_games: usize,
+    pub seed: u64,
+}
+
+impl Default for TrainConfig {
+    fn default() -> Self {
+        Self {
+            episodes: 100_000,
+            learning_rate: 0.5,
+            discount: 0.9,
+            epsilon: 0.5,
+            eval_every: 10_000,
+            eval_games: 20,
+            seed: 0,
+        }
+    }
+}
+
+/// Values learned for every move played, from the point of view of
+/// the player making the move.
+#[derive(Debug, Clone, Default)]
+pub struct QPolicy {
+    values: HashMap<(ClassicBoard, usize), f64>,
+}
+
+impl QPolicy {
+    /// The legal move with the highest value; `None` if the game ended.
+    pub fn choose_move(&self, game: &Game) -> Option<CellCoord> {
+        game.legal_moves()
+            .max_by(|a, b| self.q_value(game, *a).total_cmp(&self.q_value(game, *b)))
+    }
+
+    pub fn q_value(&self, game: &Game, (x, y): CellCoord) -> f64 {
+        *self
+            .values
+            .get(&(game.board, cell_index(x, y)))
+            .unwrap_or(&0.0)
+    }
+
+    /// Best value among the game's legal moves.
+    fn max_q(&self, game: &Game) -> f64 {
+        game.legal_moves()
+            .map(|m| self.q_value(game, m))
+            .fold(None, |max: Option<f64>, q| {
+                Some(max.map_or(q, |m| m.max(q)))
+            })
+            .unwrap_or(0.0)
+    }
+}
+
+/// Results of the learned policy against the perfect player.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Evaluation {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+impl Evaluation {
+    fn games(&self) -> f64 {
+        (self.wins + self.draws + self.losses) as f64
+    }
+
+    pub fn win_rate(&self) -> f64 {
+        self.wins as f64 / self.games()
+    }
+
+    pub fn draw_rate(&self) -> f64 {
+        self.draws as f64 / self.games()
+    }
+
+    pub fn loss_rate(&self) -> f64 {
+        self.losses as f64 / self.games()
+    }
+}
+
+pub struct Trainer {
+    config: TrainConfig,
+    policy: QPolicy,
+    rng: StdRng,
+}
+
+impl Trainer {
+    pub fn new(config: TrainConfig) -> Result<Self, TrainError> {
+        if config.eval_every == 0 {
+            return Err(TrainError::NoEvaluationInterval);
+        }
+
+        if config.eval_games == 0 {
+            return Err(TrainError::NoEvaluationGames);
+        }
+
+        Ok(Self {
+            rng: StdRng::seed_from_u64(config.seed),
+            config,
+            policy: QPolicy::default(),
+        })
+    }
+
+    /// Runs all configured episodes, writing the learning curve to
+    /// `curve` as CSV: one row per evaluation.
+    pub fn train<W: Write>(&mut self, curve: &mut W) -> io::Result<()> {
+        writeln!(curve, "episodes,win_rate,draw_rate,loss_rate")?;
+
+        for episode in 1..=self.config.episodes {
+            self.run_episode();
+
+            if episode % self.config.eval_every == 0 {
+                let eval = self.evaluate();
+
+                writeln!(
+                    curve,
+                    "{},{:.3},{:.3},{:.3}",
+                    episode,
+                    eval.win_rate(),
+                    eval.draw_rate(),
+                    eval.loss_rate()
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Plays one game against itself and updates the values of
+    /// every move made.
+    pub fn run_episode(&mut self) {
+        let mut game = Game::new();
+
+        while !game.ended() {
+            let moves: Vec<_> = game.legal_moves().collect();
+
+            let chosen = if self.rng.gen::<f64>() < self.config.epsilon {
+                *moves.choose(&mut self.rng).unwrap()
+            } else {
+                self.policy.choose_move(&game).unwrap()
+            };
+
+            let mut next = game.clone();
+            next.mark(chosen.0, chosen.1).unwrap();
+
+            let target = match next.get_outcome() {
+                Some(Outcome::Draw(_)) => 0.0,
+                Some(_) => 1.0,
+                // whatever is good for the opponent is bad for the mover
+                None => -self.config.discount * self.policy.max_q(&next),
+            };
+
+            let key = (game.board, cell_index(chosen.0, chosen.1));
+            let q = self.policy.values.entry(key).or_insert(0.0);
+            *q += self.config.learning_rate * (target - *q);
+
+            game = next;
+        }
+    }
+
+    /// Plays the current policy against the perfect player, which
+    /// picks at random between its best moves.
+    pub fn evaluate(&mut self) -> Evaluation {
+        let mut eval = Evaluation::default();
+
+        for &side in &[XorZ::X, XorZ::Z] {
+            for _ in 0..self.config.eval_games {
+                let mut game = Game::new();
+
+                while !game.ended() {
+                    let (x, y) = if game.turn() == side {
+                        self.policy.choose_move(&game).unwrap()
+                    } else {
+                        let best: Vec<_> = tablebase::lookup_board(&game.board)
+                            .unwrap()
+                            .best_moves()
+                            .collect();
+
+                        *best.choose(&mut self.rng).unwrap()
+                    };
+
+                    game.mark(x, y).unwrap();
+                }
+
+                match game.get_outcome().and_then(Outcome::winner) {
+                    Some(winner) if winner == side => eval.wins += 1,
+                    Some(_) => eval.losses += 1,
+                    None => eval.draws += 1,
+                }
+            }
+        }
+
+        eval
+    }
+
+    pub fn policy(&self) -> &QPolicy {
+        &self.policy
+    }
+
+    pub fn into_policy(self) -> QPolicy {
+        self.policy
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn learning_curve() {
+        let mut trainer = Trainer::new(TrainConfig {
+            episodes: 5_000,
+            eval_every: 1_000,
+            ..TrainConfig::default()
+        })
+        .unwrap();
+
+        let mut csv = Vec::new();
+        trainer.train(&mut csv).unwrap();
+
+        let csv = String::from_utf8(csv).unwrap();
+        let rows: Vec<_> = csv.lines().collect();
+
+        assert_eq!(rows.len(), 6);
+        assert_eq!(rows[0], "episodes,win_rate,draw_rate,loss_rate");
+
+        let loss_rate = |row: &str| row.rsplit(',').next().unwrap().parse::<f64>().unwrap();
+
+        // a few thousand episodes are enough to lose less often, though
+        // not to play well yet
+        assert!(loss_rate(rows[5]) < loss_rate(rows[1]), "{}", csv);
+    }
+
+    #[test]
+    fn invalid_config() {
+        let config = |eval_every, eval_games| {
+            Trainer::new(TrainConfig {
+                eval_every,
+                eval_games,
+                ..TrainConfig::default()
+            })
+            .err()
+        };
+
+        assert_eq!(config(0, 20), Some(TrainError::NoEvaluationInterval));
+        assert_eq!(config(100, 0), Some(TrainError::NoEvaluationGames));
+        assert_eq!(config(100, 20), None);
+    }
+}
@@ -1,7 +1,7 @@
 use quicksilver::{
-    geom::{Shape, Transform, Vector},
+    geom::{Rectangle, Shape, Transform, Vector},
     graphics::{
-        Background::{Blended, Img},
+        Background::{Blended, Col, Img},
         Color, Font, Image,
     },
     input::{Mouse, MouseButton},
@@ -9,7 +9,10 @@ use quicksilver::{
     Result as QSResult,
 };
 
-use lib_tac_toe::{CellState, Game, Outcome, WinLine, XorZ};
+use lib_tac_toe::{
+    player::{GameDriver, QueuedPlayer},
+    CellCoord, CellState, Game, GameBuilder, Outcome, WinLine, XorZ,
+};
 
 mod assets;
 mod buttons;
@@ -25,6 +28,16 @@ const BG_COLOR: &str = "f2eecb";
 const GRID_PADDING: f32 = 46.5;
 const MARK_SHADOW_ALPHA: f32 = 0.09;
 
+const BLOCKED_CELLS: usize = 2;
+const BLOCKED_COLOR: Color = Color {
+    r: 0.2,
+    g: 0.2,
+    b: 0.4,
+    a: 0.25,
+};
+/// blocked cells are drawn a bit smaller than the cell
+const BLOCKED_SCALE: f32 = 0.7;
+
 // win line angles; a bit skewed
 const HWL_ANGLE: i16 = 88;
 const VWL_ANGLE: i16 = -2;
@@ -37,11 +50,16 @@ const DWL_SCALE: (f32, f32) = (1.0, 1.3);
 const BTNS_WIDTH: f32 = 250.0;
 const BTNS_PADDING: f32 = 30.0;
 
+/// Both players click on the same board.
+type LocalGame = GameDriver<QueuedPlayer, QueuedPlayer>;
+
 pub struct TicTacToe {
     pub grid: Grid,
     assets: GameAssets,
-    game: Game,
+    game: LocalGame,
     buttons: GameButtons,
+    /// number of games started, used as the seed for blocked cells
+    games: u64,
 }
 
 impl State for TicTacToe {
@@ -52,7 +70,7 @@ impl State for TicTacToe {
             .with_line_size(assets.line_size())
             .with_reference_point((0.0, 0.0))
             .build();
-        let game = Game::new();
+        let game = Self::new_game();
 
         let btns_tl = Vector::new(grid.total_width, 0.0);
         let btns_br = Vector::new(grid.total_width + BTNS_WIDTH, grid.total_height);
@@ -63,6 +81,7 @@ impl State for TicTacToe {
             grid,
             game,
             buttons,
+            games: 0,
         })
     }
 
@@ -83,30 +102,65 @@ impl State for TicTacToe {
     fn update(&mut self, window: &mut Window) -> QSResult<()> {
         self.handle_mouse(window.mouse());
 
+        if !self.game.game().ended() {
+            // clicks on marked or blocked cells are simply ignored
+            let _ = self.game.step();
+        }
+
         Ok(())
     }
 }
 
 impl TicTacToe {
+    fn new_game() -> LocalGame {
+        Self::local_game(Game::new())
+    }
+
+    fn new_blocked_game(seed: u64) -> LocalGame {
+        let game = GameBuilder::new()
+            .with_random_blocked_cells(BLOCKED_CELLS, seed)
+            .build()
+            .unwrap();
+
+        Self::local_game(game)
+    }
+
+    fn local_game(game: Game) -> LocalGame {
+        GameDriver::new(game, QueuedPlayer::new(), QueuedPlayer::new())
+    }
+
     fn new_buttons(top_left: Vector, bottom_right: Vector, font: &Font) -> QSResult<GameButtons> {
         let mut buttons = GameButtons::new(top_left, bottom_right, BTNS_PADDING);
 
         let new_game_btn = GameButton::new(font, "NEW GAME", Command::NewGame)?;
         buttons.add_button(new_game_btn);
 
+        let blocked_game_btn = GameButton::new(font, "BLOCKED", Command::NewBlockedGame)?;
+        buttons.add_button(blocked_game_btn);
+
         Ok(buttons)
     }
 
     fn draw_game_state(&self, window: &mut Window) {
-        let board = self.game.board_state();
+        let board = self.game.game().board_state();
 
         for (i, column) in board.iter().enumerate() {
             for (j, cell) in column.iter().enumerate() {
-                if let CellState::Marked(mark) = cell {
-                    let img = self.x_z_image(*mark);
-                    let center = self.grid.cells[i][j].mid;
+                let grid_cell = &self.grid.cells[i][j];
 
-                    window.draw(&img.area().with_center(center), Img(img));
+                match cell {
+                    CellState::Marked(mark) => {
+                        let img = self.x_z_image(*mark);
+
+                        window.draw(&img.area().with_center(grid_cell.mid), Img(img));
+                    }
+                    CellState::Blocked => {
+                        let size = (grid_cell.bottom_right - grid_cell.top_left) * BLOCKED_SCALE;
+                        let area = Rectangle::new((0.0, 0.0), size).with_center(grid_cell.mid);
+
+                        window.draw(&area, Col(BLOCKED_COLOR));
+                    }
+                    CellState::Empty => {}
                 }
             }
         }
@@ -115,26 +169,30 @@ impl TicTacToe {
     }
 
     fn draw_win_line(&self, window: &mut Window) {
-        if let Some(Outcome::Win(_, wl)) = self.game.get_outcome() {
-            let (center, angle) = self.win_line_center_angle(wl);
-
-            window.draw_ex(
-                &self.assets.line.area().with_center(center),
-                Img(&self.assets.line),
-                Transform::rotate(angle) * Transform::scale(self.win_line_scale(wl)),
-                0,
-            );
+        if let Some(lines) = self.game.game().get_outcome().and_then(Outcome::win_lines) {
+            for wl in lines.iter() {
+                let (center, angle) = self.win_line_center_angle(wl);
+
+                window.draw_ex(
+                    &self.assets.line.area().with_center(center),
+                    Img(&self.assets.line),
+                    Transform::rotate(angle) * Transform::scale(self.win_line_scale(wl)),
+                    0,
+                );
+            }
         }
     }
 
     fn draw_mark_shadow(&self, window: &mut Window) {
-        if self.game.ended() {
+        let game = self.game.game();
+
+        if game.ended() {
             return;
         }
 
         if let Some((coord, cell)) = self.grid.cell_containing(window.mouse().pos()) {
-            if !self.game.is_marked(coord.0, coord.1) {
-                let img = self.x_z_image(self.game.turn());
+            if !game.is_marked(coord.0, coord.1) && !game.is_blocked(coord.0, coord.1) {
+                let img = self.x_z_image(game.turn());
                 let color = Color::from_rgba(0, 0, 0, MARK_SHADOW_ALPHA);
 
                 window.draw(&img.area().with_center(cell.mid), Blended(img, color));
@@ -184,7 +242,7 @@ impl TicTacToe {
 
         if mouse[MouseButton::Left].is_down() {
             if let Some((coord, _)) = self.grid.cell_containing(position) {
-                let _ = self.game.mark(coord.0, coord.1);
+                self.queue_click(coord);
             }
 
             if let Some(command) = self.buttons.btn_command(position) {
@@ -193,11 +251,26 @@ impl TicTacToe {
         }
     }
 
+    /// Hands the clicked cell to the player whose turn it is.
+    fn queue_click(&mut self, coord: CellCoord) {
+        let player = match self.game.game().turn() {
+            XorZ::X => self.game.x_player_mut(),
+            XorZ::Z => self.game.z_player_mut(),
+        };
+
+        // a held down button only counts once
+        player.clear();
+        player.push(coord);
+    }
+
     fn handle_command(&mut self, command: Command) {
         use Command::*;
 
+        self.games += 1;
+
         match command {
-            NewGame => self.game = Game::new(),
+            NewGame => self.game = Self::new_game(),
+            NewBlockedGame => self.game = Self::new_blocked_game(self.games),
         }
     }
 }
@@ -1,4 +1,6 @@
 #[derive(Debug, Clone, Copy)]
 pub enum Command {
     NewGame,
+    /// a new game with some cells blocked at random
+    NewBlockedGame,
 }
@@ -0,0 +1,193 @@
+//! JavaScript bindings for lib-tac-toe.
+//!
+//! Players are `"X"` and `"0"` and cells are `x`, `y` pairs counted
+//! from the top left corner. Failures are thrown as `Error`s whose
+//! `name` is the Rust error type, e.g. `MarkError`.
+//!
+//! The tests run in node: `wasm-pack test --node`.
+use std::fmt::Display;
+
+use js_sys::Array;
+use wasm_bindgen::prelude::*;
+
+use lib_tac_toe::player::{PerfectPlayer, Player};
+use lib_tac_toe::{self as lib, record, CellState, DrawReason, Outcome, WinReason, XorZ};
+
+const ROW_SIZE: usize = 3;
+
+#[wasm_bindgen]
+pub struct Game {
+    game: lib::Game,
+}
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cell {
+    pub x: usize,
+    pub y: usize,
+}
+
+#[wasm_bindgen]
+impl Game {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Game {
+        Game {
+            game: lib::Game::new(),
+        }
+    }
+
+    /// A game from position notation, e.g. `X0./.X./... 0`, with `#`
+    /// for blocked cells.
+    #[wasm_bindgen(js_name = fromPosition)]
+    pub fn from_position(text: &str) -> Result<Game, JsValue> {
+        let game = record::read_position(text).map_err(|e| js_error("SetupError", e))?;
+
+        Ok(Game { game })
+    }
+
+    /// The position notation of the game.
+    pub fn position(&self) -> String {
+        record::position_name(&self.game)
+    }
+
+    /// Marks the cell for the player whose turn it is; returns
+    /// whether the game ended.
+    pub fn mark(&mut self, x: usize, y: usize) -> Result<bool, JsValue> {
+        let outcome = self.game.mark(x, y).map_err(|e| js_error("MarkError", e))?;
+
+        Ok(outcome.is_some())
+    }
+
+    /// The player to move.
+    pub fn turn(&self) -> String {
+        player_name(self.game.turn())
+    }
+
+    /// `"X"`, `"0"`, `"#"` for a blocked cell or `""` for an empty one.
+    pub fn cell(&self, x: usize, y: usize) -> Result<String, JsValue> {
+        if x >= ROW_SIZE || y >= ROW_SIZE {
+            return Err(js_error("RangeError", "Cell out of bounds"));
+        }
+
+        Ok(cell_name(self.game.board_state()[x][y]))
+    }
+
+    /// All cells, as returned by `cell`, row by row from the top.
+    pub fn board(&self) -> Array {
+        let state = self.game.board_state();
+
+        (0..ROW_SIZE)
+            .flat_map(|y| (0..ROW_SIZE).map(move |x| (x, y)))
+            .map(|(x, y)| JsValue::from(cell_name(state[x][y])))
+            .collect()
+    }
+
+    pub fn ended(&self) -> bool {
+        self.game.ended()
+    }
+
+    /// `"X"` or `"0"`; `undefined` while the game goes on and after
+    /// a draw.
+    pub fn winner(&self) -> Option<String> {
+        self.game
+            .get_outcome()
+            .and_then(Outcome::winner)
+            .map(player_name)
+    }
+
+    /// Why the game ended: `lines`, `resignation`, `timeout` or
+    /// `disconnect` for wins, `boardFull`, `deadPosition`,
+    /// `forcedDraw` or `agreement` for draws; `undefined` while the
+    /// game goes on.
+    pub fn reason(&self) -> Option<String> {
+        let reason = match self.game.get_outcome()? {
+            Outcome::Win(_, WinReason::Lines(_)) => "lines",
+            Outcome::Win(_, WinReason::Resignation) => "resignation",
+            Outcome::Win(_, WinReason::Timeout) => "timeout",
+            Outcome::Win(_, WinReason::Disconnect) => "disconnect",
+            Outcome::Draw(DrawReason::BoardFull) => "boardFull",
+            Outcome::Draw(DrawReason::DeadPosition) => "deadPosition",
+            Outcome::Draw(DrawReason::ForcedDraw) => "forcedDraw",
+            Outcome::Draw(DrawReason::Agreement) => "agreement",
+        };
+
+        Some(reason.to_string())
+    }
+
+    /// The `Cell`s of the completed lines; empty unless the game was
+    /// won on the board.
+    #[wasm_bindgen(js_name = winningCells)]
+    pub fn winning_cells(&self) -> Array {
+        let lines = self.game.get_outcome().and_then(Outcome::win_lines);
+        let mut cells: Vec<_> = lines
+            .into_iter()
+            .flat_map(|lines| lines.iter())
+            .flat_map(|line| line.cells().to_vec())
+            .collect();
+
+        cells.sort_unstable();
+        cells.dedup();
+
+        cells
+            .into_iter()
+            .map(|(x, y)| JsValue::from(Cell { x, y }))
+            .collect()
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Plays perfectly, picking at random between equally good moves.
+#[wasm_bindgen]
+pub struct Ai {
+    player: PerfectPlayer,
+}
+
+#[wasm_bindgen]
+impl Ai {
+    #[wasm_bindgen(constructor)]
+    pub fn new(seed: u32) -> Ai {
+        Ai {
+            player: PerfectPlayer::new(seed.into()),
+        }
+    }
+
+    /// The move for the player to move; `undefined` once the game
+    /// ended.
+    #[wasm_bindgen(js_name = chooseMove)]
+    pub fn choose_move(&mut self, game: &Game) -> Result<Option<Cell>, JsValue> {
+        let choice = self
+            .player
+            .choose_move(&game.game)
+            .map_err(|e| js_error("Error", e))?;
+
+        Ok(choice.map(|(x, y)| Cell { x, y }))
+    }
+}
+
+fn player_name(player: XorZ) -> String {
+    match player {
+        XorZ::X => "X",
+        XorZ::Z => "0",
+    }
+    .to_string()
+}
+
+fn cell_name(cell: CellState) -> String {
+    match cell {
+        CellState::Empty => String::new(),
+        CellState::Marked(player) => player_name(player),
+        CellState::Blocked => "#".to_string(),
+    }
+}
+
+fn js_error<E: Display>(name: &str, error: E) -> JsValue {
+    let js = js_sys::Error::new(&error.to_string());
+    js.set_name(name);
+
+    js.into()
+}
@@ -0,0 +1,109 @@
+//! Run with `wasm-pack test --node`.
+#![cfg(target_arch = "wasm32")]
+
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::*;
+
+use wasm_tac_toe::{Ai, Cell, Game};
+
+#[wasm_bindgen_test]
+fn plays_a_game() {
+    let mut game = Game::new();
+
+    for (x, y) in [(0, 0), (1, 1), (1, 0), (2, 2)].iter() {
+        assert!(!game.mark(*x, *y).unwrap());
+    }
+
+    assert_eq!(game.turn(), "X");
+    assert_eq!(game.cell(1, 1).unwrap(), "0");
+    assert_eq!(game.cell(2, 0).unwrap(), "");
+    assert_eq!(game.board().length(), 9);
+    assert_eq!(game.winner(), None);
+
+    assert!(game.mark(2, 0).unwrap());
+    assert!(game.ended());
+    assert_eq!(game.winner().as_deref(), Some("X"));
+    assert_eq!(game.reason().as_deref(), Some("lines"));
+
+    let cells: Vec<Cell> = game
+        .winning_cells()
+        .iter()
+        .map(|cell| {
+            let x = js_sys::Reflect::get(&cell, &"x".into()).unwrap();
+            let y = js_sys::Reflect::get(&cell, &"y".into()).unwrap();
+
+            Cell {
+                x: x.as_f64().unwrap() as usize,
+                y: y.as_f64().unwrap() as usize,
+            }
+        })
+        .collect();
+
+    assert_eq!(
+        cells,
+        vec![
+            Cell { x: 0, y: 0 },
+            Cell { x: 1, y: 0 },
+            Cell { x: 2, y: 0 }
+        ]
+    );
+}
+
+#[wasm_bindgen_test]
+fn errors_are_js_errors() {
+    let mut game = Game::new();
+    game.mark(1, 1).unwrap();
+
+    let error: js_sys::Error = game.mark(1, 1).unwrap_err().dyn_into().unwrap();
+    assert_eq!(error.name(), "MarkError");
+    assert_eq!(error.message(), "Cell is already marked!");
+
+    let error: js_sys::Error = Game::from_position("XX/... X")
+        .err()
+        .unwrap()
+        .dyn_into()
+        .unwrap();
+    assert_eq!(error.name(), "SetupError");
+
+    assert!(game.cell(3, 0).is_err());
+}
+
+#[wasm_bindgen_test]
+fn positions() {
+    let game = Game::from_position("X0./.X./... 0").unwrap();
+
+    assert_eq!(game.turn(), "0");
+    assert_eq!(game.cell(1, 0).unwrap(), "0");
+    assert_eq!(game.position(), "X0./.X./... 0");
+
+    let mut blocked = Game::from_position("X#./.0./... X").unwrap();
+
+    assert_eq!(blocked.cell(1, 0).unwrap(), "#");
+
+    let error: js_sys::Error = blocked.mark(1, 0).unwrap_err().dyn_into().unwrap();
+    assert_eq!(error.message(), "Cell is blocked!");
+}
+
+#[wasm_bindgen_test]
+fn ai_blocks() {
+    let game = Game::from_position("XX./0../... 0").unwrap();
+    let mut ai = Ai::new(1);
+
+    assert_eq!(ai.choose_move(&game).unwrap(), Some(Cell { x: 2, y: 0 }));
+}
+
+#[wasm_bindgen_test]
+fn ais_draw() {
+    let mut game = Game::new();
+    let (mut x, mut z) = (Ai::new(1), Ai::new(2));
+
+    while !game.ended() {
+        let ai = if game.turn() == "X" { &mut x } else { &mut z };
+        let cell = ai.choose_move(&game).unwrap().unwrap();
+
+        game.mark(cell.x, cell.y).unwrap();
+    }
+
+    assert_eq!(game.winner(), None);
+    assert_eq!(x.choose_move(&game).unwrap(), None);
+}